@@ -22,4 +22,16 @@ pub trait Encryption {
         Self::SecretLength: ArrayLength<u8>;
     fn encrypt(&mut self, data: &[u8]) -> Result<Vec<u8>, Error>;
     fn decrypt(&mut self, data: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// encrypt into the passed buffer, replacing its content. implementations can reuse the memory of the buffer.
+    fn encrypt_into(&mut self, data: &[u8], encrypted: &mut Vec<u8>) -> Result<(), Error> {
+        *encrypted = self.encrypt(data)?;
+        Ok(())
+    }
+
+    /// decrypt into the passed buffer, replacing its content. implementations can reuse the memory of the buffer.
+    fn decrypt_into(&mut self, data: &[u8], decrypted: &mut Vec<u8>) -> Result<(), Error> {
+        *decrypted = self.decrypt(data)?;
+        Ok(())
+    }
 }
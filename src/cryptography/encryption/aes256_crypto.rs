@@ -1,5 +1,5 @@
 use aes_gcm::{
-    aead::{AeadMut, OsRng},
+    aead::{AeadMut, AeadMutInPlace, OsRng},
     AeadCore, Aes256Gcm, KeyInit, Nonce,
 };
 use generic_array::{typenum::U32, GenericArray};
@@ -8,6 +8,7 @@ use super::Encryption;
 
 pub const NONCE_SIZE: usize = 12;
 
+#[derive(Clone)]
 pub struct Aes256Crypto {
     crypto: Aes256Gcm,
 }
@@ -51,6 +52,34 @@ impl Encryption for Aes256Crypto {
         Ok(decrypted)
     }
 
+    fn encrypt_into(&mut self, data: &[u8], encrypted: &mut Vec<u8>) -> Result<(), super::Error> {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        encrypted.clear();
+        encrypted.extend_from_slice(data);
+        if let Err(e) = self.crypto.encrypt_in_place(&nonce, b"", encrypted) {
+            return Err(super::Error::Encryption(e.to_string()));
+        }
+
+        encrypted.extend_from_slice(&nonce);
+        Ok(())
+    }
+
+    fn decrypt_into(&mut self, data: &[u8], decrypted: &mut Vec<u8>) -> Result<(), super::Error> {
+        if data.len() < NONCE_SIZE {
+            return Err(super::Error::Encryption("Encrypted message does not contain nonce.".to_string()));
+        }
+
+        let nonce_start = data.len() - NONCE_SIZE;
+        let nonce = Nonce::from_slice(&data[nonce_start..]);
+        decrypted.clear();
+        decrypted.extend_from_slice(&data[..nonce_start]);
+        if let Err(e) = self.crypto.decrypt_in_place(nonce, b"", decrypted) {
+            return Err(super::Error::Encryption(e.to_string()));
+        }
+
+        Ok(())
+    }
+
     fn initialize(shared_secret: &GenericArray<u8, U32>) -> Result<Box<Self>, super::Error> {
         Ok(Box::new(Self::new(shared_secret)))
     }
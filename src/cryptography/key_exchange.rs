@@ -1,11 +1,15 @@
 pub mod curve25519;
 
 use std::fmt::Display;
+#[cfg(feature = "tokio")]
+use std::future::Future;
 
 use displaydoc::Display;
 use generic_array::{ArrayLength, GenericArray};
 use thiserror::Error;
 
+#[cfg(feature = "tokio")]
+use crate::async_connection::AsyncConnection;
 use crate::connection::Connection;
 
 pub enum HandshakeMode {
@@ -32,3 +36,16 @@ pub trait KeyExchange {
     where
         Self::SecretLength: ArrayLength<u8>;
 }
+
+#[cfg(feature = "tokio")]
+pub trait AsyncKeyExchange {
+    type SecretLength;
+
+    fn handshake_async<E: Display>(
+        &mut self,
+        connection: &mut (impl AsyncConnection<ErrorType = E> + Send),
+        mode: HandshakeMode,
+    ) -> impl Future<Output = Result<GenericArray<u8, Self::SecretLength>, Error>> + Send
+    where
+        Self::SecretLength: ArrayLength<u8>;
+}
@@ -4,10 +4,15 @@ use generic_array::{typenum::U32, GenericArray};
 use rand_core::OsRng;
 use x25519_dalek::{EphemeralSecret, PublicKey};
 
+#[cfg(feature = "tokio")]
+use crate::async_connection::AsyncConnection;
 use crate::connection::Connection;
 
+#[cfg(feature = "tokio")]
+use super::AsyncKeyExchange;
 use super::{Error, KeyExchange};
 
+#[derive(Clone, Copy)]
 pub struct Curve25519;
 
 const PUB_KEY_BYTE_SIZE: usize = 32;
@@ -29,14 +34,41 @@ impl KeyExchange for Curve25519 {
 
         let pub_key_data = connection.receive().map_err(|e| Error::Communication(e.to_string()))?;
 
-        let remote_pub_key: [u8; PUB_KEY_BYTE_SIZE] = match pub_key_data.try_into() {
-            Ok(v) => v,
-            Err(_) => return Err(super::Error::Handshake("Invalid remote public key size".to_string())),
-        };
+        shared_secret(private_key, pub_key_data)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncKeyExchange for Curve25519 {
+    type SecretLength = U32;
+
+    async fn handshake_async<E: Display>(
+        &mut self,
+        connection: &mut (impl AsyncConnection<ErrorType = E> + Send),
+        _mode: super::HandshakeMode,
+    ) -> Result<GenericArray<u8, U32>, Error> {
+        let private_key = EphemeralSecret::new(OsRng);
+        let public_key = PublicKey::from(&private_key);
+
+        connection
+            .send(public_key.as_bytes())
+            .await
+            .map_err(|e| Error::Communication(e.to_string()))?;
 
-        // calculate shared secret
-        Ok(GenericArray::from(
-            private_key.diffie_hellman(&PublicKey::from(remote_pub_key)).to_bytes(),
-        ))
+        let pub_key_data = connection.receive().await.map_err(|e| Error::Communication(e.to_string()))?;
+
+        shared_secret(private_key, pub_key_data)
     }
 }
+
+fn shared_secret(private_key: EphemeralSecret, pub_key_data: Vec<u8>) -> Result<GenericArray<u8, U32>, Error> {
+    let remote_pub_key: [u8; PUB_KEY_BYTE_SIZE] = match pub_key_data.try_into() {
+        Ok(v) => v,
+        Err(_) => return Err(super::Error::Handshake("Invalid remote public key size".to_string())),
+    };
+
+    // calculate shared secret
+    Ok(GenericArray::from(
+        private_key.diffie_hellman(&PublicKey::from(remote_pub_key)).to_bytes(),
+    ))
+}
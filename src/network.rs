@@ -1,3 +1,23 @@
+#[cfg(feature = "tokio")]
+pub mod async_connection;
+pub mod chaos_connection;
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+pub mod compressed_connection;
 pub mod connection;
+pub mod datagram_connection;
 pub mod encrypted_connection;
+pub mod heartbeat_connection;
+pub mod memory_connection;
+pub mod multiplexer;
 pub mod packet_connection;
+pub mod packet_listener;
+pub mod pubsub;
+#[cfg(feature = "reactor")]
+pub mod reactor;
+pub mod reconnecting_connection;
+pub mod recording_connection;
+pub mod reliable_connection;
+pub mod replay_connection;
+pub mod rpc;
+#[cfg(feature = "serde")]
+pub mod typed_connection;
@@ -0,0 +1,11 @@
+use std::future::Future;
+
+/// async counterpart of `Connection` for connections driven by tokio.
+pub trait AsyncConnection {
+    type ErrorType;
+
+    fn send(&mut self, data: &[u8]) -> impl Future<Output = Result<(), Self::ErrorType>> + Send;
+
+    /// receiving is cancel safe, so it can be wrapped in e.g. `tokio::time::timeout` without losing partial packets.
+    fn receive(&mut self) -> impl Future<Output = Result<Vec<u8>, Self::ErrorType>> + Send;
+}
@@ -0,0 +1,129 @@
+pub mod chaos_config;
+mod chaos_rng;
+
+use std::{thread, time::Duration};
+
+use crate::connection::{Connection, TimedReceive};
+
+use chaos_config::ChaosConfig;
+use chaos_rng::ChaosRng;
+
+/// number of faults a `ChaosConnection` injected so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub sent: u64,
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub reordered: u64,
+    pub truncated: u64,
+    pub bit_flipped: u64,
+}
+
+/// injects faults into the sent packets to test how protocols deal with adverse conditions.
+///
+/// only the sending direction is disturbed, wrap both ends to disturb both directions.
+/// received packets and errors of the underlying connection are passed through unchanged.
+pub struct ChaosConnection<Con> {
+    connection: Con,
+    config: ChaosConfig,
+    rng: ChaosRng,
+    held_back: Option<Vec<u8>>,
+    stats: ChaosStats,
+}
+
+impl<Con: Connection> ChaosConnection<Con> {
+    pub fn new(connection: Con, config: ChaosConfig) -> Self {
+        Self {
+            connection,
+            rng: ChaosRng::new(config.seed),
+            config,
+            held_back: None,
+            stats: ChaosStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> ChaosStats {
+        self.stats
+    }
+
+    /// send the packet that was held back to reorder it, if there is one.
+    pub fn flush(&mut self) -> Result<(), Con::ErrorType> {
+        match self.held_back.take() {
+            Some(packet) => self.connection.send(&packet),
+            None => Ok(()),
+        }
+    }
+
+    /// get the underlying connection.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    fn random_delay(&mut self) -> Option<Duration> {
+        let (min, max) = self.config.delay?;
+        let range = (max - min).as_nanos() as u64;
+        Some(min + Duration::from_nanos(self.rng.below(range.saturating_add(1))))
+    }
+}
+
+impl<Con: Connection> Connection for ChaosConnection<Con> {
+    type ErrorType = Con::ErrorType;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Con::ErrorType> {
+        self.stats.sent += 1;
+        if self.stats.sent <= self.config.skipped_packets {
+            return self.connection.send(data);
+        }
+        if let Some(delay) = self.random_delay() {
+            thread::sleep(delay);
+        }
+
+        if self.rng.chance(self.config.drop_probability) {
+            self.stats.dropped += 1;
+            return Ok(());
+        }
+
+        let mut packet = data.to_vec();
+        if !packet.is_empty() && self.rng.chance(self.config.truncate_probability) {
+            packet.truncate(self.rng.below(packet.len() as u64) as usize);
+            self.stats.truncated += 1;
+        }
+        if !packet.is_empty() && self.rng.chance(self.config.bit_flip_probability) {
+            let bit = self.rng.below(packet.len() as u64 * 8) as usize;
+            packet[bit / 8] ^= 1 << (bit % 8);
+            self.stats.bit_flipped += 1;
+        }
+        let duplicate = self.rng.chance(self.config.duplicate_probability);
+
+        if self.held_back.is_none() && self.rng.chance(self.config.reorder_probability) {
+            self.stats.reordered += 1;
+            self.held_back = Some(packet);
+            return Ok(());
+        }
+
+        self.connection.send(&packet)?;
+        if duplicate {
+            self.stats.duplicated += 1;
+            self.connection.send(&packet)?;
+        }
+        self.flush()
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>, Con::ErrorType> {
+        self.connection.receive()
+    }
+
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), Con::ErrorType> {
+        self.connection.receive_into(buffer)
+    }
+}
+
+impl<Con: TimedReceive> TimedReceive for ChaosConnection<Con> {
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Con::ErrorType> {
+        self.connection.receive_timeout(timeout)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Con::ErrorType> {
+        self.connection.try_receive()
+    }
+}
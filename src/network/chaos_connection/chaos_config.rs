@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+/// which faults a `ChaosConnection` injects and how often. all probabilities default to 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChaosConfig {
+    pub(super) seed: u64,
+    pub(super) drop_probability: f64,
+    pub(super) duplicate_probability: f64,
+    pub(super) reorder_probability: f64,
+    pub(super) truncate_probability: f64,
+    pub(super) bit_flip_probability: f64,
+    pub(super) delay: Option<(Duration, Duration)>,
+    pub(super) skipped_packets: u64,
+}
+
+impl ChaosConfig {
+    /// the same seed and settings produce the same faults for the same sequence of packets.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            drop_probability: 0.0,
+            duplicate_probability: 0.0,
+            reorder_probability: 0.0,
+            truncate_probability: 0.0,
+            bit_flip_probability: 0.0,
+            delay: None,
+            skipped_packets: 0,
+        }
+    }
+
+    /// the packet is not sent at all.
+    pub fn with_drop_probability(mut self, probability: f64) -> Self {
+        self.drop_probability = checked_probability(probability);
+        self
+    }
+
+    /// the packet is sent twice.
+    pub fn with_duplicate_probability(mut self, probability: f64) -> Self {
+        self.duplicate_probability = checked_probability(probability);
+        self
+    }
+
+    /// the packet is held back and sent after the next one.
+    pub fn with_reorder_probability(mut self, probability: f64) -> Self {
+        self.reorder_probability = checked_probability(probability);
+        self
+    }
+
+    /// the end of the packet is cut off at a random position.
+    pub fn with_truncate_probability(mut self, probability: f64) -> Self {
+        self.truncate_probability = checked_probability(probability);
+        self
+    }
+
+    /// a single random bit of the packet is flipped.
+    pub fn with_bit_flip_probability(mut self, probability: f64) -> Self {
+        self.bit_flip_probability = checked_probability(probability);
+        self
+    }
+
+    /// sending each packet is delayed by a random duration between `min` and `max`.
+    pub fn with_delay(mut self, min: Duration, max: Duration) -> Self {
+        assert!(min <= max, "minimum delay {min:?} exceeds the maximum delay {max:?}");
+        self.delay = Some((min, max));
+        self
+    }
+
+    /// send the first `count` packets unchanged, e.g. to let a handshake pass.
+    pub fn with_skipped_packets(mut self, count: u64) -> Self {
+        self.skipped_packets = count;
+        self
+    }
+}
+
+fn checked_probability(probability: f64) -> f64 {
+    assert!((0.0..=1.0).contains(&probability), "probability {probability} is not between 0 and 1");
+    probability
+}
@@ -0,0 +1,29 @@
+/// small deterministic random number generator (splitmix64). the same seed always yields the same faults.
+pub struct ChaosRng {
+    state: u64,
+}
+
+impl ChaosRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniformly distributed in `0..bound`. `bound` has to be greater than 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// true with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        // 53 random bits give a uniformly distributed float in [0, 1)
+        probability > 0.0 && ((self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64) < probability
+    }
+}
@@ -0,0 +1,191 @@
+pub mod compression;
+mod constants;
+
+use std::{fmt::Display, time::Duration};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use compression::Compression;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Failed to compress packet: {0}
+    Compress(String),
+    /// Failed to decompress packet: {0}
+    Decompress(String),
+    /// Packet is too short to contain a compression header
+    InvalidPacket,
+    /// Packet was compressed with an unsupported algorithm (flag {flag})
+    UnsupportedCompression { flag: u8 },
+    /// Decompressed packet of {size} bytes exceeds the limit of {limit} bytes
+    PacketTooLarge { size: usize, limit: usize },
+}
+
+/// compresses packets before they are passed to the underlying connection.
+///
+/// every packet starts with a flag byte, packets below the threshold and packets that do not shrink are sent
+/// uncompressed. to combine it with encryption, wrap the `EncryptedConnection`: encrypted data does not compress.
+pub struct CompressedConnection<Con> {
+    connection: Con,
+    compression: Compression,
+    threshold: usize,
+    max_packet_size: usize,
+    send_buffer: Vec<u8>,
+    receive_buffer: Vec<u8>,
+    payload_bytes: u64,
+    wire_bytes: u64,
+}
+
+impl<Con, E> CompressedConnection<Con>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    pub fn new(connection: Con, compression: Compression) -> Self {
+        Self {
+            connection,
+            compression,
+            threshold: constants::DEFAULT_THRESHOLD,
+            max_packet_size: constants::DEFAULT_MAX_PACKET_SIZE,
+            send_buffer: Vec::new(),
+            receive_buffer: Vec::new(),
+            payload_bytes: 0,
+            wire_bytes: 0,
+        }
+    }
+
+    /// packets smaller than the threshold are sent uncompressed.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// largest packet the remote is allowed to announce. bigger packets fail with `Error::PacketTooLarge`.
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    /// ratio between the size of the sent packets and the bytes passed to the underlying connection.
+    /// values above 1 mean compression saved space, 1 is returned before anything was sent.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.wire_bytes == 0 {
+            return 1.0;
+        }
+        self.payload_bytes as f64 / self.wire_bytes as f64
+    }
+
+    /// get the underlying connection.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    // writes the packet into the send buffer, compressed if that makes it smaller
+    fn encode(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.send_buffer.clear();
+        if data.len() >= self.threshold && data.len() <= u32::MAX as usize {
+            self.send_buffer.push(self.compression.flag());
+            self.send_buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            self.compression.compress(data, &mut self.send_buffer)?;
+            if self.send_buffer.len() < data.len() + 1 {
+                return Ok(());
+            }
+            self.send_buffer.clear();
+        }
+
+        self.send_buffer.push(constants::FLAG_UNCOMPRESSED);
+        self.send_buffer.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn decode(&self, packet: &[u8], destination: &mut Vec<u8>) -> Result<(), Error> {
+        let (&flag, data) = packet.split_first().ok_or(Error::InvalidPacket)?;
+        if flag == constants::FLAG_UNCOMPRESSED {
+            destination.clear();
+            destination.extend_from_slice(data);
+            return Ok(());
+        }
+
+        if packet.len() < constants::COMPRESSED_HEADER_SIZE {
+            return Err(Error::InvalidPacket);
+        }
+        let size = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        if size > self.max_packet_size {
+            return Err(Error::PacketTooLarge {
+                size,
+                limit: self.max_packet_size,
+            });
+        }
+        compression::decompress(flag, &data[4..], size, destination)
+    }
+
+    fn decode_optional(&self, packet: Option<Vec<u8>>) -> Result<Option<Vec<u8>>, Error> {
+        match packet {
+            Some(packet) => {
+                let mut decoded = Vec::new();
+                self.decode(&packet, &mut decoded)?;
+                Ok(Some(decoded))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<Con, E> Connection for CompressedConnection<Con>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = Error;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.encode(data)?;
+        self.connection.send(&self.send_buffer).map_err(connection_error)?;
+
+        self.payload_bytes += data.len() as u64;
+        self.wire_bytes += self.send_buffer.len() as u64;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        let mut packet = Vec::new();
+        self.receive_into(&mut packet)?;
+        Ok(packet)
+    }
+
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        let mut receive_buffer = std::mem::take(&mut self.receive_buffer);
+        let result = self
+            .connection
+            .receive_into(&mut receive_buffer)
+            .map_err(connection_error)
+            .and_then(|_| self.decode(&receive_buffer, buffer));
+        self.receive_buffer = receive_buffer;
+        result
+    }
+}
+
+impl<Con, E> TimedReceive for CompressedConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let packet = self.connection.receive_timeout(timeout).map_err(connection_error)?;
+        self.decode_optional(packet)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let packet = self.connection.try_receive().map_err(connection_error)?;
+        self.decode_optional(packet)
+    }
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
@@ -0,0 +1,84 @@
+use super::{constants, Error};
+
+/// algorithm that is used to compress outgoing packets.
+/// incoming packets are decompressed with the algorithm the remote used, as long as it is compiled in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// better ratio, the level ranges from 1 (fast) to 22 (small).
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+    /// very fast with a moderate ratio, suited for high packet rates.
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Compression {
+    #[cfg(feature = "zstd")]
+    pub fn zstd() -> Self {
+        Compression::Zstd {
+            level: constants::DEFAULT_ZSTD_LEVEL,
+        }
+    }
+
+    pub(super) fn flag(&self) -> u8 {
+        match self {
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => constants::FLAG_ZSTD,
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => constants::FLAG_LZ4,
+        }
+    }
+
+    // appends the compressed data to the destination
+    pub(super) fn compress(&self, data: &[u8], destination: &mut Vec<u8>) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                zstd::stream::copy_encode(data, destination, *level).map_err(|e| Error::Compress(e.to_string()))
+            }
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => {
+                let offset = destination.len();
+                destination.resize(offset + lz4_flex::block::get_maximum_output_size(data.len()), 0);
+                let size = lz4_flex::block::compress_into(data, &mut destination[offset..])
+                    .map_err(|e| Error::Compress(e.to_string()))?;
+                destination.truncate(offset + size);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// decompress a packet that was compressed with the algorithm behind the flag.
+/// the destination is replaced with exactly `size` bytes.
+pub(super) fn decompress(flag: u8, data: &[u8], size: usize, destination: &mut Vec<u8>) -> Result<(), Error> {
+    destination.clear();
+    match flag {
+        #[cfg(feature = "zstd")]
+        constants::FLAG_ZSTD => {
+            use std::io::Read;
+
+            // reading one byte more than announced is enough to detect a lying remote
+            zstd::stream::read::Decoder::with_buffer(data)
+                .and_then(|decoder| decoder.take(size as u64 + 1).read_to_end(destination))
+                .map_err(|e| Error::Decompress(e.to_string()))?;
+        }
+        #[cfg(feature = "lz4")]
+        constants::FLAG_LZ4 => {
+            destination.resize(size, 0);
+            let decompressed =
+                lz4_flex::block::decompress_into(data, destination).map_err(|e| Error::Decompress(e.to_string()))?;
+            destination.truncate(decompressed);
+        }
+        _ => return Err(Error::UnsupportedCompression { flag }),
+    }
+
+    if destination.len() != size {
+        return Err(Error::Decompress(format!(
+            "decompressed {} bytes, but {} bytes were announced",
+            destination.len(),
+            size
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+/// packets smaller than this are sent uncompressed by default. compressing them rarely pays off.
+pub const DEFAULT_THRESHOLD: usize = 256;
+
+/// default upper limit of a decompressed packet. protects the receiver against decompression bombs.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+
+/// compression level that is used by `Compression::zstd`.
+#[cfg(feature = "zstd")]
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// first byte of every packet, tells the receiver how the remaining bytes are encoded.
+/// the values are part of the wire format and stay reserved if the algorithm is not compiled in.
+pub const FLAG_UNCOMPRESSED: u8 = 0;
+#[cfg(feature = "zstd")]
+pub const FLAG_ZSTD: u8 = 1;
+#[cfg(feature = "lz4")]
+pub const FLAG_LZ4: u8 = 2;
+
+/// size of the flag byte and the length of the original packet that precede compressed data.
+pub const COMPRESSED_HEADER_SIZE: usize = 1 + 4;
@@ -1,6 +1,59 @@
+use std::time::Duration;
+
 pub trait Connection {
     type ErrorType;
 
     fn send(&mut self, data: &[u8]) -> Result<(), Self::ErrorType>;
     fn receive(&mut self) -> Result<Vec<u8>, Self::ErrorType>;
+
+    /// receive a packet into the passed buffer, replacing its content.
+    /// connections that are able to reuse the memory of the buffer avoid allocating for each packet.
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), Self::ErrorType> {
+        *buffer = self.receive()?;
+        Ok(())
+    }
+}
+
+/// connection that can wait for packets with a timeout or check for them without blocking.
+/// kept apart from `Connection` so implementors that only support blocking receives are not forced to provide it.
+pub trait TimedReceive: Connection {
+    /// wait at most `timeout` for a packet. returns `None` if no packet arrived in time.
+    /// the connection stays usable after a timeout.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Self::ErrorType>;
+
+    /// receive a packet if one is available without blocking. returns `None` otherwise.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Self::ErrorType>;
+}
+
+/// sending half of a split connection.
+pub trait ConnectionSender {
+    type ErrorType;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Self::ErrorType>;
+}
+
+/// receiving half of a split connection. behaves like the receiving part of `Connection`.
+pub trait ConnectionReceiver {
+    type ErrorType;
+
+    fn receive(&mut self) -> Result<Vec<u8>, Self::ErrorType>;
+
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), Self::ErrorType> {
+        *buffer = self.receive()?;
+        Ok(())
+    }
+}
+
+/// receiving half that supports timeouts. behaves like the receiving part of `TimedReceive`.
+pub trait TimedConnectionReceiver: ConnectionReceiver {
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Self::ErrorType>;
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Self::ErrorType>;
+}
+
+/// connection that can be split into independent halves, e.g. to send and receive from different threads.
+pub trait SplitConnection: Connection {
+    type Sender: ConnectionSender<ErrorType = Self::ErrorType>;
+    type Receiver: ConnectionReceiver<ErrorType = Self::ErrorType>;
+
+    fn split(self) -> Result<(Self::Sender, Self::Receiver), Self::ErrorType>;
 }
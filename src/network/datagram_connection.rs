@@ -0,0 +1,189 @@
+mod constants;
+pub mod fragment;
+pub mod fragment_assembly;
+
+use std::{
+    io::ErrorKind,
+    net::UdpSocket,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use constants::{DEFAULT_FRAGMENT_TIMEOUT, DEFAULT_MTU, MAX_DATAGRAM_SIZE};
+use fragment::{FragmentHeader, FRAGMENT_HEADER_SIZE};
+use fragment_assembly::FragmentAssembly;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO error: {0}
+    IOError(#[from] std::io::Error),
+    /// Payload of {size} bytes exceeds the limit of {limit} bytes
+    PayloadTooLarge { size: usize, limit: usize },
+}
+
+/// connection that sends packets as UDP datagrams. packets bigger than the MTU are split into fragments
+/// and reassembled by the remote. delivery is unreliable: packets can get lost, duplicated or reordered.
+pub struct DatagramConnection {
+    socket: UdpSocket,
+    mtu: usize,
+    next_packet_id: u32,
+    fragment_assembler: FragmentAssembly,
+    send_buffer: Vec<u8>,
+    receive_buffer: Vec<u8>,
+}
+
+impl DatagramConnection {
+    /// the socket has to be connected to the remote with `UdpSocket::connect`.
+    pub fn new(socket: UdpSocket) -> DatagramConnection {
+        DatagramConnection {
+            socket,
+            mtu: DEFAULT_MTU,
+            next_packet_id: 0,
+            fragment_assembler: FragmentAssembly::new(DEFAULT_FRAGMENT_TIMEOUT),
+            send_buffer: Vec::with_capacity(DEFAULT_MTU),
+            receive_buffer: vec![0_u8; MAX_DATAGRAM_SIZE],
+        }
+    }
+
+    /// set the maximum size of a single datagram, including the fragment header.
+    ///
+    /// # Panics
+    ///
+    /// panics if the MTU does not leave room for data behind the fragment header or exceeds the size of a datagram.
+    pub fn with_mtu(mut self, mtu: usize) -> DatagramConnection {
+        assert!(
+            mtu > FRAGMENT_HEADER_SIZE && mtu <= MAX_DATAGRAM_SIZE,
+            "MTU has to be between {} and {MAX_DATAGRAM_SIZE} bytes",
+            FRAGMENT_HEADER_SIZE + 1
+        );
+        self.mtu = mtu;
+        self
+    }
+
+    /// set how long a partially received packet waits for its missing fragments before it is dropped.
+    pub fn with_fragment_timeout(mut self, fragment_timeout: Duration) -> DatagramConnection {
+        self.fragment_assembler.set_fragment_timeout(fragment_timeout);
+        self
+    }
+
+    /// limit the size of packets the remote is allowed to send, 16MiB by default.
+    /// fragments of bigger packets are ignored.
+    pub fn with_max_received_packet_size(mut self, max_packet_size: usize) -> DatagramConnection {
+        self.fragment_assembler.set_max_packet_size(max_packet_size);
+        self
+    }
+
+    /// biggest packet that fits into the maximum number of fragments.
+    pub fn max_packet_size(&self) -> usize {
+        self.fragment_size() * u16::MAX as usize
+    }
+
+    /// get the underlying socket.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    fn fragment_size(&self) -> usize {
+        self.mtu - FRAGMENT_HEADER_SIZE
+    }
+
+    // returns `None` if the read timed out or would block
+    fn receive_datagram(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let size = match self.socket.recv(&mut self.receive_buffer) {
+            Ok(v) => v,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(self.fragment_assembler.push(&self.receive_buffer[..size], Instant::now()))
+    }
+}
+
+impl Connection for DatagramConnection {
+    type ErrorType = Error;
+
+    /// splits the packet into fragments if it does not fit into a single datagram.
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        let limit = self.max_packet_size();
+        if packet.len() > limit {
+            return Err(Error::PayloadTooLarge {
+                size: packet.len(),
+                limit,
+            });
+        }
+
+        let fragment_size = self.fragment_size();
+        let count = std::cmp::max(packet.len().div_ceil(fragment_size), 1) as u16;
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.wrapping_add(1);
+
+        for index in 0..count {
+            let start = index as usize * fragment_size;
+            let data = &packet[start..std::cmp::min(start + fragment_size, packet.len())];
+
+            self.send_buffer.clear();
+            self.send_buffer.extend_from_slice(&FragmentHeader { packet_id, index, count }.to_bytes());
+            self.send_buffer.extend_from_slice(data);
+            self.socket.send(&self.send_buffer)?;
+        }
+
+        Ok(())
+    }
+
+    /// blocks until a packet was fully reassembled.
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        loop {
+            if let Some(packet) = self.receive_datagram()? {
+                return Ok(packet);
+            }
+        }
+    }
+}
+
+impl TimedReceive for DatagramConnection {
+    /// uses the read timeout of the socket, which is reset to blocking afterwards.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let deadline = Instant::now() + timeout;
+        let result = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break Ok(None);
+            }
+
+            if let Err(e) = self.socket.set_read_timeout(Some(remaining)) {
+                break Err(e.into());
+            }
+            match self.receive_datagram() {
+                Ok(Some(packet)) => break Ok(Some(packet)),
+                Ok(None) => (),
+                Err(e) => break Err(e),
+            }
+        };
+
+        self.socket.set_read_timeout(None)?;
+        result
+    }
+
+    /// switches the socket to non-blocking for the call and back to blocking afterwards.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.socket.set_nonblocking(true)?;
+        let result = loop {
+            match self.socket.recv(&mut self.receive_buffer) {
+                Ok(size) => match self.fragment_assembler.push(&self.receive_buffer[..size], Instant::now()) {
+                    Some(packet) => break Ok(Some(packet)),
+                    None => continue,
+                },
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break Ok(None),
+                Err(e) => break Err(e.into()),
+            }
+        };
+
+        self.socket.set_nonblocking(false)?;
+        result
+    }
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// default size of a single datagram. small enough to pass most links on the internet without IP fragmentation.
+pub const DEFAULT_MTU: usize = 1200;
+
+/// default time a partially received packet waits for its missing fragments.
+pub const DEFAULT_FRAGMENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// default limit for reassembled packets, the same as the one of `PacketConnection`.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+
+/// upper limit for packets that are reassembled at the same time. the oldest one is dropped when exceeded.
+pub const MAX_PARTIAL_PACKETS: usize = 64;
+
+/// biggest payload a UDP datagram is able to carry.
+pub const MAX_DATAGRAM_SIZE: usize = 65535;
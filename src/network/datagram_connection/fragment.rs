@@ -0,0 +1,44 @@
+/// size of the header in front of each fragment.
+pub const FRAGMENT_HEADER_SIZE: usize = 8;
+
+/// identifies which part of which packet a datagram carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FragmentHeader {
+    /// id of the packet, shared by all of its fragments.
+    pub packet_id: u32,
+    /// position of the fragment within the packet.
+    pub index: u16,
+    /// number of fragments the packet was split into.
+    pub count: u16,
+}
+
+impl FragmentHeader {
+    pub fn to_bytes(&self) -> [u8; FRAGMENT_HEADER_SIZE] {
+        let mut bytes = [0_u8; FRAGMENT_HEADER_SIZE];
+        bytes[..4].copy_from_slice(&self.packet_id.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.index.to_le_bytes());
+        bytes[6..].copy_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+
+    /// split a datagram into its header and the fragment data.
+    /// returns `None` if the datagram is too short or the header is inconsistent.
+    pub fn parse(datagram: &[u8]) -> Option<(FragmentHeader, &[u8])> {
+        if datagram.len() < FRAGMENT_HEADER_SIZE {
+            return None;
+        }
+
+        let (header, data) = datagram.split_at(FRAGMENT_HEADER_SIZE);
+        let header = FragmentHeader {
+            packet_id: u32::from_le_bytes(header[..4].try_into().unwrap()),
+            index: u16::from_le_bytes(header[4..6].try_into().unwrap()),
+            count: u16::from_le_bytes(header[6..].try_into().unwrap()),
+        };
+
+        if header.count == 0 || header.index >= header.count {
+            return None;
+        }
+
+        Some((header, data))
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::{
+    constants::{DEFAULT_MAX_PACKET_SIZE, MAX_PARTIAL_PACKETS},
+    fragment::FragmentHeader,
+};
+
+/// collects fragments until their packet is complete. does not do any IO itself.
+/// packets whose fragments do not all arrive within the timeout are dropped.
+pub struct FragmentAssembly {
+    partial_packets: HashMap<u32, PartialPacket>,
+    fragment_timeout: Duration,
+    max_packet_size: usize,
+}
+
+struct PartialPacket {
+    fragments: Vec<Option<Vec<u8>>>,
+    missing: usize,
+    started: Instant,
+    // size of all fragments in front of the last one, known once one of them arrived
+    fragment_size: Option<usize>,
+}
+
+impl FragmentAssembly {
+    pub fn new(fragment_timeout: Duration) -> Self {
+        Self {
+            partial_packets: HashMap::new(),
+            fragment_timeout,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+        }
+    }
+
+    pub fn set_fragment_timeout(&mut self, fragment_timeout: Duration) {
+        self.fragment_timeout = fragment_timeout;
+    }
+
+    /// fragments whose header announces a bigger packet are ignored.
+    pub fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.max_packet_size = max_packet_size;
+    }
+
+    /// number of packets that are still waiting for fragments.
+    pub fn partial_packets(&self) -> usize {
+        self.partial_packets.len()
+    }
+
+    /// add a received datagram. returns the packet once all of its fragments arrived.
+    /// malformed and duplicate fragments are ignored.
+    /// a packet whose fragments do not have matching sizes is dropped.
+    pub fn push(&mut self, datagram: &[u8], now: Instant) -> Option<Vec<u8>> {
+        self.drop_expired(now);
+
+        let (header, data) = FragmentHeader::parse(datagram)?;
+        // all fragments but the last one are as big as this one or bigger
+        if header.count as usize * data.len() > self.max_packet_size {
+            return None;
+        }
+        if header.count == 1 {
+            return Some(data.to_vec());
+        }
+        // only empty packets have empty fragments and those are never split
+        if data.is_empty() {
+            return None;
+        }
+
+        if !self.partial_packets.contains_key(&header.packet_id) && self.partial_packets.len() >= MAX_PARTIAL_PACKETS {
+            self.drop_oldest();
+        }
+
+        let packet = self.partial_packets.entry(header.packet_id).or_insert_with(|| PartialPacket {
+            fragments: vec![None; header.count as usize],
+            missing: header.count as usize,
+            started: now,
+            fragment_size: None,
+        });
+
+        // a reused packet id with a different layout belongs to a stale packet
+        if packet.fragments.len() != header.count as usize {
+            return None;
+        }
+
+        if packet.fragments[header.index as usize].is_some() {
+            return None;
+        }
+        if !packet.fits(header.index as usize, data.len()) {
+            self.partial_packets.remove(&header.packet_id);
+            return None;
+        }
+        packet.fragments[header.index as usize] = Some(data.to_vec());
+        packet.missing -= 1;
+
+        if packet.missing > 0 {
+            return None;
+        }
+
+        let packet = self.partial_packets.remove(&header.packet_id)?;
+        Some(packet.fragments.into_iter().flatten().flatten().collect())
+    }
+
+    /// drop all packets that waited longer than the fragment timeout for their missing fragments.
+    pub fn drop_expired(&mut self, now: Instant) {
+        let timeout = self.fragment_timeout;
+        self.partial_packets
+            .retain(|_, packet| now.saturating_duration_since(packet.started) < timeout);
+    }
+
+    fn drop_oldest(&mut self) {
+        let oldest = self.partial_packets.iter().min_by_key(|(_, packet)| packet.started).map(|(id, _)| *id);
+        if let Some(id) = oldest {
+            self.partial_packets.remove(&id);
+        }
+    }
+}
+
+impl PartialPacket {
+    // true if the fragment size matches the fragments that arrived before. remembers the fragment size.
+    fn fits(&mut self, index: usize, size: usize) -> bool {
+        let last = self.fragments.len() - 1;
+        if index == last {
+            return self.fragment_size.is_none_or(|fragment_size| size <= fragment_size);
+        }
+
+        let fits = match self.fragment_size {
+            Some(fragment_size) => size == fragment_size,
+            None => self.fragments[last].as_ref().is_none_or(|fragment| fragment.len() <= size),
+        };
+        self.fragment_size = Some(size);
+        fits
+    }
+}
@@ -1,11 +1,18 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+    time::Duration,
+};
 
 use displaydoc::Display;
 use generic_array::ArrayLength;
+use rand_core::{OsRng, RngCore};
 use thiserror::Error;
 
+#[cfg(feature = "tokio")]
+use crate::{async_connection::AsyncConnection, key_exchange::AsyncKeyExchange};
 use crate::{
-    connection::Connection,
+    connection::{Connection, ConnectionReceiver, ConnectionSender, SplitConnection, TimedConnectionReceiver, TimedReceive},
     cryptography::{
         encryption::{self, Encryption},
         key_exchange::{self, HandshakeMode, KeyExchange},
@@ -28,13 +35,48 @@ pub enum TransmissionError {
     EncryptMessage(encryption::Error),
     /// Failed to decrypt message: {0}
     DecryptMessage(encryption::Error),
+    /// Failed to read the payload that is streamed: {0}
+    StreamRead(std::io::Error),
+    /// Failed to write the streamed payload: {0}
+    StreamWrite(std::io::Error),
+    /// Received chunk does not belong to the stream
+    InvalidStreamChunk,
+    /// Remote aborted the stream because reading the payload failed
+    StreamAborted,
 }
 
+/// plaintext size of the chunks a streamed payload is encrypted in.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// size of the header in front of each chunk: a random stream id, the total payload length and the chunk index.
+/// it is encrypted along with the chunk, so chunks can't be reordered, replayed or spliced into another stream.
+const CHUNK_HEADER_SIZE: usize = 3 * 8;
+
+/// size of the frame that starts a stream: the random stream id and the total payload length.
+const STREAM_START_SIZE: usize = 2 * 8;
+
+/// chunk index that announces an aborted stream.
+const STREAM_ABORT_INDEX: u64 = u64::MAX;
+
 /// an encrypted connection that has full modularity regarding its key exchange,
 /// the encryption that is used and the underlying connection.
 pub struct EncryptedConnection<Enc, Con> {
     crypto: Enc,
     connection: Con,
+    // reused for every packet to avoid allocations
+    send_buffer: Vec<u8>,
+    receive_buffer: Vec<u8>,
+}
+
+impl<Enc, Con> EncryptedConnection<Enc, Con> {
+    fn from_parts(crypto: Enc, connection: Con) -> Self {
+        Self {
+            crypto,
+            connection,
+            send_buffer: Vec::new(),
+            receive_buffer: Vec::new(),
+        }
+    }
 }
 
 impl<Enc, Con, N> EncryptedConnection<Enc, Con>
@@ -56,10 +98,76 @@ where
         let secret = kex.handshake(&mut connection, mode)?;
         let crypto = Enc::initialize(&secret)?;
 
-        Ok(Self {
-            connection,
-            crypto: *crypto,
-        })
+        Ok(Self::from_parts(*crypto, connection))
+    }
+
+    /// send `len` bytes from the reader without loading the whole payload into memory.
+    /// the payload is sent as a sequence of separately encrypted and authenticated chunks,
+    /// the remote has to receive it with `receive_stream`. if reading fails the stream is aborted for the remote.
+    pub fn send_stream(&mut self, mut reader: impl Read, len: usize) -> Result<(), TransmissionError> {
+        let stream_id = OsRng.next_u64();
+        let mut chunk = Vec::with_capacity(CHUNK_HEADER_SIZE + std::cmp::min(len, STREAM_CHUNK_SIZE));
+        chunk.extend_from_slice(&stream_id.to_le_bytes());
+        chunk.extend_from_slice(&(len as u64).to_le_bytes());
+        self.send(&chunk)?;
+
+        let mut remaining = len;
+        let mut index: u64 = 0;
+        while remaining > 0 {
+            let size = std::cmp::min(remaining, STREAM_CHUNK_SIZE);
+            write_chunk_header(&mut chunk, stream_id, len, index);
+            chunk.resize(CHUNK_HEADER_SIZE + size, 0);
+            if let Err(e) = reader.read_exact(&mut chunk[CHUNK_HEADER_SIZE..]) {
+                write_chunk_header(&mut chunk, stream_id, len, STREAM_ABORT_INDEX);
+                // the read error is more relevant than a failure to notify the remote
+                let _ = self.send(&chunk);
+                return Err(TransmissionError::StreamRead(e));
+            }
+            self.send(&chunk)?;
+
+            remaining -= size;
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// receive a payload that was sent with `send_stream` chunk by chunk into the writer.
+    /// each chunk is authenticated before it is written. returns the size of the payload.
+    pub fn receive_stream(&mut self, mut writer: impl Write) -> Result<usize, TransmissionError> {
+        let mut chunk = Vec::new();
+        self.receive_into(&mut chunk)?;
+        if chunk.len() != STREAM_START_SIZE {
+            return Err(TransmissionError::InvalidStreamChunk);
+        }
+        let stream_id = read_u64(&chunk[..8]);
+        let len = usize::try_from(read_u64(&chunk[8..16])).map_err(|_| TransmissionError::InvalidStreamChunk)?;
+
+        let mut remaining = len;
+        let mut index: u64 = 0;
+        let mut expected_header = Vec::with_capacity(CHUNK_HEADER_SIZE);
+        while remaining > 0 {
+            self.receive_into(&mut chunk)?;
+            let (header, payload) = match chunk.split_at_checked(CHUNK_HEADER_SIZE) {
+                Some(v) => v,
+                None => return Err(TransmissionError::InvalidStreamChunk),
+            };
+
+            write_chunk_header(&mut expected_header, stream_id, len, STREAM_ABORT_INDEX);
+            if header == expected_header && payload.is_empty() {
+                return Err(TransmissionError::StreamAborted);
+            }
+            write_chunk_header(&mut expected_header, stream_id, len, index);
+            if header != expected_header || payload.len() != std::cmp::min(remaining, STREAM_CHUNK_SIZE) {
+                return Err(TransmissionError::InvalidStreamChunk);
+            }
+
+            writer.write_all(payload).map_err(TransmissionError::StreamWrite)?;
+            remaining -= payload.len();
+            index += 1;
+        }
+
+        Ok(len)
     }
 
     /// get the underlying connection to e.g. shut it down.
@@ -69,6 +177,29 @@ where
     }
 }
 
+#[cfg(feature = "tokio")]
+impl<Enc, Con, N> EncryptedConnection<Enc, Con>
+where
+    Enc: Encryption<SecretLength = N>,
+    Con: AsyncConnection + Send,
+    <Con as AsyncConnection>::ErrorType: std::fmt::Display,
+{
+    /// async version of `with_handshake` for connections driven by tokio.
+    pub async fn with_handshake_async(
+        mut connection: Con,
+        mut kex: impl AsyncKeyExchange<SecretLength = N>,
+        mode: HandshakeMode,
+    ) -> Result<Self, HandshakeError>
+    where
+        N: ArrayLength<u8>,
+    {
+        let secret = kex.handshake_async(&mut connection, mode).await?;
+        let crypto = Enc::initialize(&secret)?;
+
+        Ok(Self::from_parts(*crypto, connection))
+    }
+}
+
 impl<Enc, Con, E> Connection for EncryptedConnection<Enc, Con>
 where
     Enc: Encryption,
@@ -79,18 +210,208 @@ where
 
     /// send data that will be encrypted with the crypto module.
     fn send(&mut self, data: &[u8]) -> Result<(), TransmissionError> {
-        let encrypted = self.crypto.encrypt(data).map_err(TransmissionError::EncryptMessage)?;
-        self.connection
-            .send(&encrypted)
-            .map_err(|e| TransmissionError::Connection(e.to_string()))
+        self.crypto
+            .encrypt_into(data, &mut self.send_buffer)
+            .map_err(TransmissionError::EncryptMessage)?;
+        self.connection.send(&self.send_buffer).map_err(connection_error)
     }
 
     /// receive data and decrypt it with the crypto module.
     fn receive(&mut self) -> Result<Vec<u8>, TransmissionError> {
-        let packet = self
-            .connection
-            .receive()
-            .map_err(|e| TransmissionError::Connection(e.to_string()))?;
+        let mut packet = Vec::new();
+        self.receive_into(&mut packet)?;
+        Ok(packet)
+    }
+
+    /// receive data and decrypt it into the passed buffer.
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), TransmissionError> {
+        self.connection.receive_into(&mut self.receive_buffer).map_err(connection_error)?;
+        self.crypto
+            .decrypt_into(&self.receive_buffer, buffer)
+            .map_err(TransmissionError::DecryptMessage)
+    }
+}
+
+impl<Enc, Con, E> TimedReceive for EncryptedConnection<Enc, Con>
+where
+    Enc: Encryption,
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    /// receive data within the timeout and decrypt it with the crypto module.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, TransmissionError> {
+        let packet = self.connection.receive_timeout(timeout).map_err(connection_error)?;
+        decrypt_optional(&mut self.crypto, packet)
+    }
+
+    /// receive data if available and decrypt it with the crypto module.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, TransmissionError> {
+        let packet = self.connection.try_receive().map_err(connection_error)?;
+        decrypt_optional(&mut self.crypto, packet)
+    }
+}
+
+impl<Enc, Con, E> SplitConnection for EncryptedConnection<Enc, Con>
+where
+    Enc: Encryption + Clone,
+    Con: SplitConnection<ErrorType = E>,
+    E: Display,
+{
+    type Sender = EncryptedSender<Enc, Con::Sender>;
+    type Receiver = EncryptedReceiver<Enc, Con::Receiver>;
+
+    /// split into a sending and a receiving half that can be used from different threads.
+    /// each half gets its own copy of the crypto module.
+    fn split(self) -> Result<(Self::Sender, Self::Receiver), TransmissionError> {
+        let (sender, receiver) = self.connection.split().map_err(connection_error)?;
+
+        Ok((
+            EncryptedSender {
+                crypto: self.crypto.clone(),
+                sender,
+                send_buffer: self.send_buffer,
+            },
+            EncryptedReceiver {
+                crypto: self.crypto,
+                receiver,
+                receive_buffer: self.receive_buffer,
+            },
+        ))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<Enc, Con, E> AsyncConnection for EncryptedConnection<Enc, Con>
+where
+    Enc: Encryption + Send,
+    Con: AsyncConnection<ErrorType = E> + Send,
+    E: Display,
+{
+    type ErrorType = TransmissionError;
+
+    /// send data that will be encrypted with the crypto module.
+    async fn send(&mut self, data: &[u8]) -> Result<(), TransmissionError> {
+        self.crypto
+            .encrypt_into(data, &mut self.send_buffer)
+            .map_err(TransmissionError::EncryptMessage)?;
+        self.connection.send(&self.send_buffer).await.map_err(connection_error)
+    }
+
+    /// receive data and decrypt it with the crypto module.
+    async fn receive(&mut self) -> Result<Vec<u8>, TransmissionError> {
+        let packet = self.connection.receive().await.map_err(connection_error)?;
         self.crypto.decrypt(&packet).map_err(TransmissionError::DecryptMessage)
     }
 }
+
+/// sending half of a split `EncryptedConnection`.
+pub struct EncryptedSender<Enc, Snd> {
+    crypto: Enc,
+    sender: Snd,
+    send_buffer: Vec<u8>,
+}
+
+impl<Enc, Snd> EncryptedSender<Enc, Snd> {
+    /// get the underlying sender.
+    /// all traffic that is sent via the sender is NOT ENCRYPTED and readable by attackers.
+    pub fn get_underlying_sender(&mut self) -> &mut Snd {
+        &mut self.sender
+    }
+}
+
+impl<Enc, Snd, E> ConnectionSender for EncryptedSender<Enc, Snd>
+where
+    Enc: Encryption,
+    Snd: ConnectionSender<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = TransmissionError;
+
+    /// send data that will be encrypted with the crypto module.
+    fn send(&mut self, data: &[u8]) -> Result<(), TransmissionError> {
+        self.crypto
+            .encrypt_into(data, &mut self.send_buffer)
+            .map_err(TransmissionError::EncryptMessage)?;
+        self.sender.send(&self.send_buffer).map_err(connection_error)
+    }
+}
+
+/// receiving half of a split `EncryptedConnection`.
+pub struct EncryptedReceiver<Enc, Rcv> {
+    crypto: Enc,
+    receiver: Rcv,
+    receive_buffer: Vec<u8>,
+}
+
+impl<Enc, Rcv> EncryptedReceiver<Enc, Rcv> {
+    /// get the underlying receiver.
+    pub fn get_underlying_receiver(&mut self) -> &mut Rcv {
+        &mut self.receiver
+    }
+}
+
+impl<Enc, Rcv, E> ConnectionReceiver for EncryptedReceiver<Enc, Rcv>
+where
+    Enc: Encryption,
+    Rcv: ConnectionReceiver<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = TransmissionError;
+
+    /// receive data and decrypt it with the crypto module.
+    fn receive(&mut self) -> Result<Vec<u8>, TransmissionError> {
+        let mut packet = Vec::new();
+        self.receive_into(&mut packet)?;
+        Ok(packet)
+    }
+
+    /// receive data and decrypt it into the passed buffer.
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), TransmissionError> {
+        self.receiver.receive_into(&mut self.receive_buffer).map_err(connection_error)?;
+        self.crypto
+            .decrypt_into(&self.receive_buffer, buffer)
+            .map_err(TransmissionError::DecryptMessage)
+    }
+}
+
+impl<Enc, Rcv, E> TimedConnectionReceiver for EncryptedReceiver<Enc, Rcv>
+where
+    Enc: Encryption,
+    Rcv: TimedConnectionReceiver<ErrorType = E>,
+    E: Display,
+{
+    /// receive data within the timeout and decrypt it with the crypto module.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, TransmissionError> {
+        let packet = self.receiver.receive_timeout(timeout).map_err(connection_error)?;
+        decrypt_optional(&mut self.crypto, packet)
+    }
+
+    /// receive data if available and decrypt it with the crypto module.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, TransmissionError> {
+        let packet = self.receiver.try_receive().map_err(connection_error)?;
+        decrypt_optional(&mut self.crypto, packet)
+    }
+}
+
+// replaces the content of the buffer with the header of a stream chunk
+fn write_chunk_header(buffer: &mut Vec<u8>, stream_id: u64, len: usize, index: u64) {
+    buffer.clear();
+    buffer.extend_from_slice(&stream_id.to_le_bytes());
+    buffer.extend_from_slice(&(len as u64).to_le_bytes());
+    buffer.extend_from_slice(&index.to_le_bytes());
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data.try_into().expect("slice has the size of a u64"))
+}
+
+fn connection_error(e: impl Display) -> TransmissionError {
+    TransmissionError::Connection(e.to_string())
+}
+
+fn decrypt_optional(crypto: &mut impl Encryption, packet: Option<Vec<u8>>) -> Result<Option<Vec<u8>>, TransmissionError> {
+    packet
+        .map(|v| crypto.decrypt(&v))
+        .transpose()
+        .map_err(TransmissionError::DecryptMessage)
+}
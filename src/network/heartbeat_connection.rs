@@ -0,0 +1,175 @@
+mod constants;
+pub mod heartbeat;
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use heartbeat::Heartbeat;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Remote did not respond within {timeout:?}
+    PeerTimeout { timeout: Duration },
+    /// Received a frame with an unknown type or invalid size
+    InvalidFrame,
+}
+
+/// detects dead peers, e.g. half-open TCP connections that would otherwise block `receive` forever.
+///
+/// a ping is sent when nothing was received for the interval, receiving fails with `Error::PeerTimeout`
+/// if the remote does not respond within the timeout. pings are only sent and answered while receiving,
+/// so both sides have to use the heartbeat layer and receive regularly.
+pub struct HeartbeatConnection<Con> {
+    connection: Con,
+    heartbeat: Heartbeat,
+    send_buffer: Vec<u8>,
+}
+
+impl<Con, E> HeartbeatConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    pub fn new(connection: Con) -> Self {
+        Self {
+            connection,
+            heartbeat: Heartbeat::default(),
+            send_buffer: Vec::new(),
+        }
+    }
+
+    /// time without any received packet after which a ping is sent.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat.set_interval(interval);
+        self
+    }
+
+    /// time the remote has to answer a ping.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.heartbeat.set_timeout(timeout);
+        self
+    }
+
+    /// round trip time measured with the last answered ping. `None` until the first pong arrived.
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        self.heartbeat.round_trip_time()
+    }
+
+    /// get the underlying connection.
+    /// packets that are sent via the connection directly are not understood by the remote heartbeat layer.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+}
+
+impl<Con, E> Connection for HeartbeatConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = Error;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        Heartbeat::encode_data(data, &mut self.send_buffer);
+        self.connection.send(&self.send_buffer).map_err(connection_error)
+    }
+
+    /// blocks until application data arrives or the remote is considered dead.
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        loop {
+            if let Some(packet) = receive_packet(&mut self.connection, &mut self.heartbeat, None)? {
+                return Ok(packet);
+            }
+        }
+    }
+}
+
+impl<Con, E> TimedReceive for HeartbeatConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        receive_packet(&mut self.connection, &mut self.heartbeat, Some(Instant::now() + timeout))
+    }
+
+    /// answers pings that already arrived without blocking.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        while let Some(frame) = self.connection.try_receive().map_err(connection_error)? {
+            let data = self.heartbeat.handle_frame(&frame, Instant::now())?;
+            send_outgoing(&mut self.connection, &mut self.heartbeat)?;
+            if let Some(range) = data {
+                return Ok(Some(frame[range].to_vec()));
+            }
+        }
+        self.heartbeat.poll(Instant::now())?;
+        send_outgoing(&mut self.connection, &mut self.heartbeat)?;
+        Ok(None)
+    }
+}
+
+/// receive the next data packet while keeping the heartbeat going.
+/// returns `None` if the deadline passed. without a deadline it returns as soon as any frame was processed.
+pub(crate) fn receive_packet<Con, E>(
+    connection: &mut Con,
+    heartbeat: &mut Heartbeat,
+    deadline: Option<Instant>,
+) -> Result<Option<Vec<u8>>, Error>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    loop {
+        let now = Instant::now();
+        heartbeat.poll(now)?;
+        send_outgoing(connection, heartbeat)?;
+
+        let wake_up = match (deadline, heartbeat.next_timeout()) {
+            (Some(a), Some(b)) => std::cmp::min(a, b),
+            (a, b) => a.or(b).unwrap_or(now),
+        };
+        if let Some(mut frame) = connection
+            .receive_timeout(wake_up.saturating_duration_since(now))
+            .map_err(connection_error)?
+        {
+            let data = heartbeat.handle_frame(&frame, Instant::now())?;
+            send_outgoing(connection, heartbeat)?;
+            match data {
+                Some(range) => {
+                    frame.drain(..range.start);
+                    return Ok(Some(frame));
+                }
+                None if deadline.is_none() => return Ok(None),
+                None => {}
+            }
+        }
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(None);
+        }
+    }
+}
+
+fn send_outgoing<Con, E>(connection: &mut Con, heartbeat: &mut Heartbeat) -> Result<(), Error>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    while let Some(frame) = heartbeat.next_outgoing() {
+        connection.send(&frame).map_err(connection_error)?;
+    }
+    Ok(())
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+/// default time without any received packet after which a ping is sent.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// default time the remote has to answer a ping before it is considered dead.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// first byte of every packet, separates application data from control frames.
+pub const FRAME_DATA: u8 = 0;
+pub const FRAME_PING: u8 = 1;
+pub const FRAME_PONG: u8 = 2;
+
+/// size of a ping or pong frame: the frame type followed by a u64 nonce.
+pub const CONTROL_FRAME_SIZE: usize = 1 + 8;
@@ -0,0 +1,139 @@
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use super::{
+    constants::{CONTROL_FRAME_SIZE, DEFAULT_INTERVAL, DEFAULT_TIMEOUT, FRAME_DATA, FRAME_PING, FRAME_PONG},
+    Error,
+};
+
+/// state machine of the keepalive layer. does not do any IO itself: pings and pongs that have to be sent
+/// are queued up until they are taken via `next_outgoing`, received frames are passed to `handle_frame`.
+pub struct Heartbeat {
+    interval: Duration,
+    timeout: Duration,
+    last_received: Option<Instant>,
+    pending_ping: Option<PendingPing>,
+    next_nonce: u64,
+    round_trip_time: Option<Duration>,
+    outgoing: VecDeque<[u8; CONTROL_FRAME_SIZE]>,
+}
+
+struct PendingPing {
+    nonce: u64,
+    sent_at: Instant,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new(DEFAULT_INTERVAL, DEFAULT_TIMEOUT)
+    }
+}
+
+impl Heartbeat {
+    /// a ping is sent after `interval` without any received frame.
+    /// the remote is considered dead if nothing arrives within `timeout` after the ping.
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            last_received: None,
+            pending_ping: None,
+            next_nonce: 0,
+            round_trip_time: None,
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// round trip time measured with the last answered ping.
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        self.round_trip_time
+    }
+
+    /// write a data frame with the payload into the destination, replacing its content.
+    pub fn encode_data(data: &[u8], destination: &mut Vec<u8>) {
+        destination.clear();
+        destination.push(FRAME_DATA);
+        destination.extend_from_slice(data);
+    }
+
+    /// process a received frame. returns the range of the application data inside the frame,
+    /// `None` for control frames.
+    pub fn handle_frame(&mut self, frame: &[u8], now: Instant) -> Result<Option<Range<usize>>, Error> {
+        self.last_received = Some(now);
+
+        match frame.first() {
+            Some(&FRAME_DATA) => Ok(Some(1..frame.len())),
+            Some(&FRAME_PING) => {
+                self.outgoing.push_back(control_frame(FRAME_PONG, parse_nonce(frame)?));
+                Ok(None)
+            }
+            Some(&FRAME_PONG) => {
+                let nonce = parse_nonce(frame)?;
+                if let Some(ping) = self.pending_ping.take_if(|ping| ping.nonce == nonce) {
+                    self.round_trip_time = Some(now.saturating_duration_since(ping.sent_at));
+                }
+                Ok(None)
+            }
+            _ => Err(Error::InvalidFrame),
+        }
+    }
+
+    /// queue a ping if the connection is idle. fails with `Error::PeerTimeout` if the remote did not respond in time.
+    pub fn poll(&mut self, now: Instant) -> Result<(), Error> {
+        let last_received = *self.last_received.get_or_insert(now);
+
+        if let Some(ping) = &self.pending_ping {
+            if now < ping.sent_at + self.timeout {
+                return Ok(());
+            }
+            if last_received < ping.sent_at {
+                return Err(Error::PeerTimeout { timeout: self.timeout });
+            }
+            // the remote sent something else after the ping, so it is alive even without the pong
+            self.pending_ping = None;
+        }
+
+        if now >= last_received + self.interval {
+            let nonce = self.next_nonce;
+            self.next_nonce = self.next_nonce.wrapping_add(1);
+            self.pending_ping = Some(PendingPing { nonce, sent_at: now });
+            self.outgoing.push_back(control_frame(FRAME_PING, nonce));
+        }
+        Ok(())
+    }
+
+    /// point in time at which `poll` has to be called next.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        match &self.pending_ping {
+            Some(ping) => Some(ping.sent_at + self.timeout),
+            None => self.last_received.map(|last_received| last_received + self.interval),
+        }
+    }
+
+    /// next control frame that has to be sent to the remote.
+    pub fn next_outgoing(&mut self) -> Option<[u8; CONTROL_FRAME_SIZE]> {
+        self.outgoing.pop_front()
+    }
+}
+
+fn control_frame(frame_type: u8, nonce: u64) -> [u8; CONTROL_FRAME_SIZE] {
+    let mut frame = [frame_type; CONTROL_FRAME_SIZE];
+    frame[1..].copy_from_slice(&nonce.to_le_bytes());
+    frame
+}
+
+fn parse_nonce(frame: &[u8]) -> Result<u64, Error> {
+    let nonce = frame.get(1..CONTROL_FRAME_SIZE).ok_or(Error::InvalidFrame)?;
+    Ok(u64::from_le_bytes(nonce.try_into().unwrap()))
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError},
+    time::Duration,
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::connection::{Connection, ConnectionReceiver, ConnectionSender, SplitConnection, TimedConnectionReceiver, TimedReceive};
+
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum Error {
+    /// Remote side of the connection was dropped
+    Disconnected,
+}
+
+/// in-process connection, e.g. for tests or to connect components of the same program.
+/// every packet is copied once into the channel and handed over as it is, without any framing.
+pub struct MemoryConnection {
+    sender: MemorySender,
+    receiver: MemoryReceiver,
+}
+
+/// sending half of a split `MemoryConnection`.
+pub struct MemorySender {
+    sender: PacketSender,
+}
+
+/// receiving half of a split `MemoryConnection`.
+pub struct MemoryReceiver {
+    receiver: Receiver<Vec<u8>>,
+}
+
+enum PacketSender {
+    Unbounded(mpsc::Sender<Vec<u8>>),
+    Bounded(SyncSender<Vec<u8>>),
+}
+
+impl MemoryConnection {
+    /// two connected ends without a limit for packets in transit.
+    pub fn pair() -> (MemoryConnection, MemoryConnection) {
+        let (local_sender, remote_receiver) = mpsc::channel();
+        let (remote_sender, local_receiver) = mpsc::channel();
+        (
+            MemoryConnection::new(PacketSender::Unbounded(local_sender), local_receiver),
+            MemoryConnection::new(PacketSender::Unbounded(remote_sender), remote_receiver),
+        )
+    }
+
+    /// two connected ends where `send` blocks while `capacity` packets wait for the remote to receive them.
+    /// with a capacity of 0 every `send` waits until the remote receives the packet.
+    pub fn bounded_pair(capacity: usize) -> (MemoryConnection, MemoryConnection) {
+        let (local_sender, remote_receiver) = mpsc::sync_channel(capacity);
+        let (remote_sender, local_receiver) = mpsc::sync_channel(capacity);
+        (
+            MemoryConnection::new(PacketSender::Bounded(local_sender), local_receiver),
+            MemoryConnection::new(PacketSender::Bounded(remote_sender), remote_receiver),
+        )
+    }
+
+    fn new(sender: PacketSender, receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            sender: MemorySender { sender },
+            receiver: MemoryReceiver { receiver },
+        }
+    }
+}
+
+impl Connection for MemoryConnection {
+    type ErrorType = Error;
+
+    /// fails with `Error::Disconnected` once the remote was dropped.
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.sender.send(data)
+    }
+
+    /// packets that were sent before the remote was dropped are still received.
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        self.receiver.receive()
+    }
+}
+
+impl TimedReceive for MemoryConnection {
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        self.receiver.receive_timeout(timeout)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.receiver.try_receive()
+    }
+}
+
+impl SplitConnection for MemoryConnection {
+    type Sender = MemorySender;
+    type Receiver = MemoryReceiver;
+
+    fn split(self) -> Result<(MemorySender, MemoryReceiver), Error> {
+        Ok((self.sender, self.receiver))
+    }
+}
+
+impl ConnectionSender for MemorySender {
+    type ErrorType = Error;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        match &self.sender {
+            PacketSender::Unbounded(sender) => sender.send(data.to_vec()).map_err(|_| Error::Disconnected),
+            PacketSender::Bounded(sender) => sender.send(data.to_vec()).map_err(|_| Error::Disconnected),
+        }
+    }
+}
+
+impl ConnectionReceiver for MemoryReceiver {
+    type ErrorType = Error;
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        self.receiver.recv().map_err(|_| Error::Disconnected)
+    }
+}
+
+impl TimedConnectionReceiver for MemoryReceiver {
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(packet) => Ok(Some(packet)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::Disconnected),
+        }
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        match self.receiver.try_recv() {
+            Ok(packet) => Ok(Some(packet)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(Error::Disconnected),
+        }
+    }
+}
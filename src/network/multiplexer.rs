@@ -0,0 +1,456 @@
+mod constants;
+pub mod frame;
+mod stream_state;
+
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use parking_lot::{Condvar, Mutex};
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use constants::{DEFAULT_MAX_MESSAGE_SIZE, MAX_FRAME_PAYLOAD, POLL_INTERVAL};
+use frame::{Frame, REMOTE_STREAM_BIT};
+use stream_state::StreamState;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Stream was closed
+    StreamClosed,
+    /// All stream ids are used up
+    StreamIdsExhausted,
+    /// Message exceeds the limit of {limit} bytes
+    MessageTooLarge { limit: usize },
+}
+
+/// hands out numbered substreams that share a single connection, e.g. an `EncryptedConnection`.
+/// each substream implements `Connection` and has its own flow control, so a large transfer on one stream
+/// does not starve the others.
+///
+/// there is no background thread: whoever waits for data reads from the underlying connection and
+/// dispatches the received frames to the streams they belong to.
+pub struct Multiplexer<Con> {
+    shared: Arc<Shared<Con>>,
+}
+
+/// a logical channel of a `Multiplexer`. dropping it closes the stream.
+pub struct Substream<Con> {
+    shared: Arc<Shared<Con>>,
+    id: u32,
+}
+
+// message with the credit that has to be granted to the remote after taking it
+type TakenMessage = (Vec<u8>, Option<u32>);
+
+struct Shared<Con> {
+    connection: Mutex<Con>,
+    state: Mutex<State>,
+    state_changed: Condvar,
+}
+
+struct State {
+    streams: HashMap<u32, StreamState>,
+    incoming_streams: VecDeque<u32>,
+    next_stream_id: u32,
+    // close frames of dropped streams, sent with the next frame
+    pending_closes: Vec<u32>,
+    max_message_size: usize,
+    error: Option<String>,
+}
+
+impl<Con> Clone for Multiplexer<Con> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<Con, E> Multiplexer<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    /// both sides of the connection have to be wrapped in a multiplexer.
+    pub fn new(connection: Con) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                connection: Mutex::new(connection),
+                state: Mutex::new(State {
+                    streams: HashMap::new(),
+                    incoming_streams: VecDeque::new(),
+                    next_stream_id: 0,
+                    pending_closes: Vec::new(),
+                    max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+                    error: None,
+                }),
+                state_changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// limit the size of messages on all streams. sending a bigger message fails right away, receiving one
+    /// discards it and closes the stream. both sides should use the same limit.
+    pub fn with_max_message_size(self, max_message_size: usize) -> Self {
+        self.shared.state.lock().max_message_size = max_message_size;
+        self
+    }
+
+    /// open a new stream. the remote gets it from `accept`.
+    pub fn open(&self) -> Result<Substream<Con>, Error> {
+        let id = {
+            let mut state = self.shared.state.lock();
+            let id = state.next_stream_id;
+            if id & REMOTE_STREAM_BIT != 0 {
+                return Err(Error::StreamIdsExhausted);
+            }
+            state.next_stream_id += 1;
+            state.streams.insert(id, StreamState::new());
+            id
+        };
+
+        self.shared.send_frame(&Frame::Open { stream: id })?;
+        Ok(Substream {
+            shared: self.shared.clone(),
+            id,
+        })
+    }
+
+    /// wait for a stream that was opened by the remote.
+    pub fn accept(&self) -> Result<Substream<Con>, Error> {
+        let id = self.shared.wait_forever(|state| state.incoming_streams.pop_front().map(Ok))?;
+        Ok(Substream {
+            shared: self.shared.clone(),
+            id,
+        })
+    }
+
+    /// wait at most `timeout` for a stream that was opened by the remote.
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<Option<Substream<Con>>, Error> {
+        self.accept_until(Some(Instant::now() + timeout))
+    }
+
+    /// take a stream that was opened by the remote without blocking.
+    pub fn try_accept(&self) -> Result<Option<Substream<Con>>, Error> {
+        self.shared.poll()?;
+        self.accept_until(Some(Instant::now()))
+    }
+
+    fn accept_until(&self, deadline: Option<Instant>) -> Result<Option<Substream<Con>>, Error> {
+        let id = self.shared.wait(deadline, |state| state.incoming_streams.pop_front().map(Ok))?;
+        Ok(id.map(|id| Substream {
+            shared: self.shared.clone(),
+            id,
+        }))
+    }
+}
+
+impl<Con> Substream<Con> {
+    /// ids are unique per multiplexer. streams that were opened by the remote have the highest bit set.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// true if the stream was opened by this side.
+    pub fn is_local(&self) -> bool {
+        self.id & REMOTE_STREAM_BIT == 0
+    }
+}
+
+impl<Con, E> Substream<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    /// close the stream for both sides. unread messages are discarded.
+    pub fn close(self) -> Result<(), Error> {
+        // the stream is removed here, so drop has nothing left to do
+        match self.shared.remove_stream(self.id) {
+            Some(false) => self.shared.send_frame(&Frame::Close { stream: self.id }),
+            _ => Ok(()),
+        }
+    }
+
+    fn receive_until(&self, deadline: Option<Instant>) -> Result<Option<Vec<u8>>, Error> {
+        let message = self.shared.wait(deadline, |state| self.take_message(state))?;
+        let message = match message {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        self.grant(message).map(Some)
+    }
+
+    fn take_message(&self, state: &mut State) -> Option<Result<TakenMessage, Error>> {
+        let stream = match state.streams.get_mut(&self.id) {
+            Some(v) => v,
+            None => return Some(Err(Error::StreamClosed)),
+        };
+
+        match stream.take_message() {
+            Some(v) => Some(Ok(v)),
+            None if stream.is_message_too_large() => Some(Err(Error::MessageTooLarge {
+                limit: state.max_message_size,
+            })),
+            None if stream.is_remote_closed() => Some(Err(Error::StreamClosed)),
+            None => None,
+        }
+    }
+
+    // hand the credit of a taken message back to the remote
+    fn grant(&self, (message, credit): TakenMessage) -> Result<Vec<u8>, Error> {
+        if let Some(credit) = credit {
+            self.shared.send_frame(&Frame::WindowUpdate { stream: self.id, credit })?;
+        }
+        Ok(message)
+    }
+}
+
+impl<Con> Drop for Substream<Con> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        if let Some(stream) = state.streams.remove(&self.id) {
+            if !stream.is_remote_closed() {
+                state.pending_closes.push(self.id);
+            }
+        }
+    }
+}
+
+impl<Con, E> Connection for Substream<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = Error;
+
+    /// big messages are sent in several frames. waits for credit if the remote did not read enough data yet.
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        let limit = self.shared.state.lock().max_message_size;
+        if data.len() > limit {
+            return Err(Error::MessageTooLarge { limit });
+        }
+
+        let mut remaining = data;
+        loop {
+            let size = self.shared.wait_forever(|state| {
+                let stream = match state.streams.get_mut(&self.id) {
+                    Some(v) if !v.is_remote_closed() => v,
+                    _ => return Some(Err(Error::StreamClosed)),
+                };
+
+                let size = remaining.len().min(MAX_FRAME_PAYLOAD).min(stream.send_credit());
+                if size == 0 && !remaining.is_empty() {
+                    return None;
+                }
+                stream.consume_send_credit(size);
+                Some(Ok(size))
+            })?;
+
+            let (payload, rest) = remaining.split_at(size);
+            self.shared.send_frame(&Frame::Data {
+                stream: self.id,
+                payload,
+                end_of_message: rest.is_empty(),
+            })?;
+
+            if rest.is_empty() {
+                return Ok(());
+            }
+            remaining = rest;
+        }
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        let message = self.shared.wait_forever(|state| self.take_message(state))?;
+        self.grant(message)
+    }
+}
+
+impl<Con, E> TimedReceive for Substream<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        self.receive_until(Some(Instant::now() + timeout))
+    }
+
+    /// processes all frames that already arrived without blocking.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.shared.poll()?;
+        self.receive_until(Some(Instant::now()))
+    }
+}
+
+impl<Con, E> Shared<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    fn send_frame(&self, frame: &Frame) -> Result<(), Error> {
+        let mut data = Vec::new();
+        frame.encode(&mut data);
+
+        let mut connection = self.connection.lock();
+        self.send_pending_closes(&mut connection)?;
+        connection.send(&data).map_err(|e| self.fail(e))
+    }
+
+    fn send_pending_closes(&self, connection: &mut Con) -> Result<(), Error> {
+        let pending_closes = std::mem::take(&mut self.state.lock().pending_closes);
+        for stream in pending_closes {
+            let mut data = Vec::new();
+            Frame::Close { stream }.encode(&mut data);
+            connection.send(&data).map_err(|e| self.fail(e))?;
+        }
+        Ok(())
+    }
+
+    // returns whether the remote closed the stream already, `None` if the stream does not exist
+    fn remove_stream(&self, id: u32) -> Option<bool> {
+        let stream = self.state.lock().streams.remove(&id)?;
+        Some(stream.is_remote_closed())
+    }
+
+    fn wait_forever<T>(&self, mut check: impl FnMut(&mut State) -> Option<Result<T, Error>>) -> Result<T, Error> {
+        loop {
+            if let Some(result) = self.wait(None, &mut check)? {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// wait until `check` returns a result. returns `None` if the deadline passed first.
+    /// while waiting, frames are read from the connection unless another thread is already reading.
+    fn wait<T>(
+        &self,
+        deadline: Option<Instant>,
+        mut check: impl FnMut(&mut State) -> Option<Result<T, Error>>,
+    ) -> Result<Option<T>, Error> {
+        loop {
+            {
+                let mut state = self.state.lock();
+                if let Some(result) = check(&mut state) {
+                    return result.map(Some);
+                }
+                if let Some(e) = &state.error {
+                    return Err(Error::Connection(e.clone()));
+                }
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.saturating_duration_since(Instant::now()) {
+                    remaining if remaining.is_zero() => return Ok(None),
+                    remaining => remaining.min(POLL_INTERVAL),
+                },
+                None => POLL_INTERVAL,
+            };
+
+            match self.connection.try_lock() {
+                Some(mut connection) => {
+                    self.send_pending_closes(&mut connection)?;
+                    let frame = connection.receive_timeout(timeout);
+                    drop(connection);
+
+                    if let Some(frame) = frame.map_err(|e| self.fail(e))? {
+                        self.dispatch(&frame)?;
+                    }
+                }
+                None => {
+                    let mut state = self.state.lock();
+                    self.state_changed.wait_for(&mut state, timeout);
+                }
+            }
+        }
+    }
+
+    // dispatch all frames that already arrived
+    fn poll(&self) -> Result<(), Error> {
+        let mut connection = self.connection.lock();
+        self.send_pending_closes(&mut connection)?;
+        let mut frames = Vec::new();
+        while let Some(frame) = connection.try_receive().map_err(|e| self.fail(e))? {
+            frames.push(frame);
+        }
+        drop(connection);
+
+        for frame in frames {
+            self.dispatch(&frame)?;
+        }
+        Ok(())
+    }
+
+    // frames that are not valid or belong to unknown streams are ignored
+    fn dispatch(&self, data: &[u8]) -> Result<(), Error> {
+        let mut state = self.state.lock();
+        let max_message_size = state.max_message_size;
+        let mut close = None;
+        let grant = match Frame::parse(data) {
+            Some(Frame::Open { stream }) => {
+                let id = stream ^ REMOTE_STREAM_BIT;
+                if let Entry::Vacant(entry) = state.streams.entry(id) {
+                    entry.insert(StreamState::new());
+                    state.incoming_streams.push_back(id);
+                }
+                None
+            }
+            Some(Frame::Data {
+                stream,
+                payload,
+                end_of_message,
+            }) => {
+                let id = stream ^ REMOTE_STREAM_BIT;
+                match state.streams.get_mut(&id) {
+                    Some(stream) if !stream.is_message_too_large() => {
+                        let grant = stream.push_data(payload, end_of_message, max_message_size);
+                        // tell the remote to stop sending once its message exceeded the limit
+                        if stream.is_message_too_large() {
+                            close = Some(id);
+                        }
+                        grant.map(|credit| (id, credit))
+                    }
+                    _ => None,
+                }
+            }
+            Some(Frame::Close { stream }) => {
+                if let Some(stream) = state.streams.get_mut(&(stream ^ REMOTE_STREAM_BIT)) {
+                    stream.set_remote_closed();
+                }
+                None
+            }
+            Some(Frame::WindowUpdate { stream, credit }) => {
+                if let Some(stream) = state.streams.get_mut(&(stream ^ REMOTE_STREAM_BIT)) {
+                    stream.add_send_credit(credit as usize);
+                }
+                None
+            }
+            None => None,
+        };
+        drop(state);
+        self.state_changed.notify_all();
+
+        if let Some(stream) = close {
+            self.send_frame(&Frame::Close { stream })?;
+        }
+        match grant {
+            Some((stream, credit)) => self.send_frame(&Frame::WindowUpdate { stream, credit }),
+            None => Ok(()),
+        }
+    }
+
+    // remember the error, so all streams fail with it
+    fn fail(&self, e: impl Display) -> Error {
+        let message = e.to_string();
+        self.state.lock().error = Some(message.clone());
+        self.state_changed.notify_all();
+        Error::Connection(message)
+    }
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// bytes a stream is allowed to send before the remote has to grant more credit.
+pub const INITIAL_WINDOW: usize = 256 * 1024;
+
+/// received bytes are granted back to the remote in batches of at least this size.
+pub const GRANT_THRESHOLD: usize = INITIAL_WINDOW / 4;
+
+/// default limit for the size of a single message, bounds the memory a stream buffers for unfinished messages.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// biggest payload of a single data frame. bigger messages are split, so other streams get a turn in between.
+pub const MAX_FRAME_PAYLOAD: usize = 16 * 1024;
+
+/// longest time the underlying connection is blocked by a waiting receiver, so senders can get in between.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(5);
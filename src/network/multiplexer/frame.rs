@@ -0,0 +1,78 @@
+const OPEN_TAG: u8 = 0;
+const DATA_TAG: u8 = 1;
+const END_OF_MESSAGE_TAG: u8 = 2;
+const CLOSE_TAG: u8 = 3;
+const WINDOW_UPDATE_TAG: u8 = 4;
+
+/// size of the tag and stream id in front of each frame.
+pub const FRAME_HEADER_SIZE: usize = 5;
+
+/// set in stream ids of streams that were opened by the remote.
+/// frames carry the id as seen by their sender, the receiver flips the bit.
+pub const REMOTE_STREAM_BIT: u32 = 1 << 31;
+
+/// unit that is sent over the multiplexed connection.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Frame<'a> {
+    /// the sender opened a new stream.
+    Open { stream: u32 },
+    /// part of a message. the last part of a message has `end_of_message` set.
+    Data {
+        stream: u32,
+        payload: &'a [u8],
+        end_of_message: bool,
+    },
+    /// the sender closed the stream, no more data is sent or received on it.
+    Close { stream: u32 },
+    /// the sender consumed data and allows the receiver to send `credit` more bytes.
+    WindowUpdate { stream: u32, credit: u32 },
+}
+
+impl<'a> Frame<'a> {
+    /// append the wire format of the frame to the passed buffer.
+    pub fn encode(&self, destination: &mut Vec<u8>) {
+        let (tag, stream) = match self {
+            Frame::Open { stream } => (OPEN_TAG, stream),
+            Frame::Data {
+                stream,
+                end_of_message: false,
+                ..
+            } => (DATA_TAG, stream),
+            Frame::Data { stream, .. } => (END_OF_MESSAGE_TAG, stream),
+            Frame::Close { stream } => (CLOSE_TAG, stream),
+            Frame::WindowUpdate { stream, .. } => (WINDOW_UPDATE_TAG, stream),
+        };
+
+        destination.push(tag);
+        destination.extend_from_slice(&stream.to_le_bytes());
+        match self {
+            Frame::Data { payload, .. } => destination.extend_from_slice(payload),
+            Frame::WindowUpdate { credit, .. } => destination.extend_from_slice(&credit.to_le_bytes()),
+            _ => (),
+        }
+    }
+
+    /// returns `None` if the data is not a valid frame.
+    pub fn parse(data: &'a [u8]) -> Option<Frame<'a>> {
+        if data.len() < FRAME_HEADER_SIZE {
+            return None;
+        }
+
+        let stream = u32::from_le_bytes(data[1..FRAME_HEADER_SIZE].try_into().unwrap());
+        let body = &data[FRAME_HEADER_SIZE..];
+        match data[0] {
+            OPEN_TAG if body.is_empty() => Some(Frame::Open { stream }),
+            DATA_TAG | END_OF_MESSAGE_TAG => Some(Frame::Data {
+                stream,
+                payload: body,
+                end_of_message: data[0] == END_OF_MESSAGE_TAG,
+            }),
+            CLOSE_TAG if body.is_empty() => Some(Frame::Close { stream }),
+            WINDOW_UPDATE_TAG => Some(Frame::WindowUpdate {
+                stream,
+                credit: u32::from_le_bytes(body.try_into().ok()?),
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use super::constants::{GRANT_THRESHOLD, INITIAL_WINDOW};
+
+/// buffered data and flow control of a single stream.
+pub struct StreamState {
+    // complete messages with the number of bytes that are granted back once the message is taken
+    messages: VecDeque<(Vec<u8>, usize)>,
+    partial_message: Vec<u8>,
+    // received bytes of the partial message that were not granted back yet
+    partial_credit: usize,
+    pending_grant: usize,
+    send_credit: usize,
+    remote_closed: bool,
+    message_too_large: bool,
+}
+
+impl StreamState {
+    pub fn new() -> Self {
+        Self {
+            messages: VecDeque::new(),
+            partial_message: Vec::new(),
+            partial_credit: 0,
+            pending_grant: 0,
+            send_credit: INITIAL_WINDOW,
+            remote_closed: false,
+            message_too_large: false,
+        }
+    }
+
+    pub fn is_remote_closed(&self) -> bool {
+        self.remote_closed
+    }
+
+    pub fn set_remote_closed(&mut self) {
+        self.remote_closed = true;
+    }
+
+    /// true if the remote sent a message bigger than the limit. the stream does not receive anything afterwards.
+    pub fn is_message_too_large(&self) -> bool {
+        self.message_too_large
+    }
+
+    /// add received data. returns credit that has to be granted to the remote.
+    /// data of an unfinished message is granted right away while no complete message waits to be taken,
+    /// otherwise messages bigger than the window would never finish. a message bigger than
+    /// `max_message_size` is discarded and ends the stream.
+    pub fn push_data(&mut self, payload: &[u8], end_of_message: bool, max_message_size: usize) -> Option<u32> {
+        if self.message_too_large {
+            return None;
+        }
+        if self.partial_message.len() + payload.len() > max_message_size {
+            self.message_too_large = true;
+            self.remote_closed = true;
+            self.partial_message = Vec::new();
+            return None;
+        }
+
+        self.partial_message.extend_from_slice(payload);
+        if end_of_message {
+            let message = std::mem::take(&mut self.partial_message);
+            let credit = std::mem::take(&mut self.partial_credit) + payload.len();
+            self.messages.push_back((message, credit));
+            return None;
+        }
+
+        if self.messages.is_empty() {
+            self.add_grant(payload.len())
+        } else {
+            self.partial_credit += payload.len();
+            None
+        }
+    }
+
+    /// take the oldest complete message together with credit that has to be granted to the remote.
+    pub fn take_message(&mut self) -> Option<(Vec<u8>, Option<u32>)> {
+        let (message, mut credit) = self.messages.pop_front()?;
+        // the remote may be waiting for credit to finish the partial message that is read next
+        if self.messages.is_empty() {
+            credit += std::mem::take(&mut self.partial_credit);
+        }
+        Some((message, self.add_grant(credit)))
+    }
+
+    pub fn send_credit(&self) -> usize {
+        self.send_credit
+    }
+
+    pub fn consume_send_credit(&mut self, size: usize) {
+        self.send_credit -= size;
+    }
+
+    pub fn add_send_credit(&mut self, credit: usize) {
+        self.send_credit += credit;
+    }
+
+    fn add_grant(&mut self, size: usize) -> Option<u32> {
+        self.pending_grant += size;
+        if self.pending_grant < GRANT_THRESHOLD {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending_grant) as u32)
+    }
+}
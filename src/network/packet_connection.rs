@@ -1,20 +1,31 @@
+#[cfg(feature = "tokio")]
+pub mod async_packet_connection;
+pub mod byte_stream;
 mod constants;
-mod packet_assembly;
+pub mod packet_assembly;
 mod packet_buffer;
+pub mod packet_codec;
+pub mod packet_halves;
 pub mod packet_receive_event;
+#[cfg(feature = "tokio-codec")]
+pub mod tokio_codec;
 
 use std::{
-    io::Write,
+    io::{ErrorKind, IoSlice, Read, Write},
     net::{Shutdown, TcpStream},
-    u8,
+    time::{Duration, Instant},
 };
 
 use displaydoc::Display;
 use thiserror::Error;
 
-use crate::connection::Connection;
+use crate::connection::{Connection, SplitConnection, TimedReceive};
 
+use byte_stream::ByteStream;
+use constants::{DEFAULT_MAX_PACKET_SIZE, MIN_READ_TIMEOUT, STREAM_CHUNK_SIZE};
 use packet_assembly::PacketAssembly;
+use packet_codec::{EncodeError, FrameChecksum, FrameFormat, PacketEncoder};
+use packet_halves::{PacketReceiver, PacketSender};
 
 #[derive(Debug, Display, Error)]
 pub enum Error {
@@ -22,51 +33,310 @@ pub enum Error {
     IOError(#[from] std::io::Error),
     /// Failed to assemble packet: {0}
     PacketAssembly(#[from] packet_assembly::Error),
+    /// Payload of {size} bytes exceeds the limit of {limit} bytes
+    PayloadTooLarge { size: usize, limit: usize },
+    /// Failed to read the payload that is streamed: {0}
+    StreamRead(std::io::Error),
 }
 
-/// TCP connection that sends and receives sized packages instead of streaming data.
-pub struct PacketConnection {
-    tcp_stream: TcpStream,
+impl From<EncodeError> for Error {
+    fn from(e: EncodeError) -> Self {
+        match e {
+            EncodeError::PayloadTooLarge { size, limit } => Error::PayloadTooLarge { size, limit },
+        }
+    }
+}
+
+/// connection that sends and receives sized packages instead of streaming data.
+/// works over any byte stream, by default a TCP stream.
+pub struct PacketConnection<S = TcpStream> {
+    stream: S,
     packet_assembler: PacketAssembly,
+    packet_encoder: PacketEncoder,
 }
 
-impl PacketConnection {
-    pub fn new(tcp_stream: TcpStream, receive_buffer_size: usize) -> PacketConnection {
+impl<S: ByteStream> PacketConnection<S> {
+    pub fn new(stream: S, receive_buffer_size: usize) -> PacketConnection<S> {
         PacketConnection {
-            tcp_stream,
-            packet_assembler: PacketAssembly::new(receive_buffer_size),
+            stream,
+            packet_assembler: PacketAssembly::new(receive_buffer_size, DEFAULT_MAX_PACKET_SIZE),
+            packet_encoder: PacketEncoder::new(),
+        }
+    }
+
+    /// limit the size of packets the remote is allowed to send, 16MiB by default.
+    /// receiving a header that announces a bigger packet fails with `packet_assembly::Error::PacketTooLarge`.
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> PacketConnection<S> {
+        self.packet_assembler.set_max_packet_size(max_packet_size);
+        self
+    }
+
+    /// use a different header format, e.g. to talk to peers that use big endian or varint size prefixes.
+    /// both sides have to use the same format.
+    pub fn with_frame_format(mut self, frame_format: FrameFormat) -> PacketConnection<S> {
+        self.packet_assembler.set_frame_format(frame_format);
+        self.packet_encoder.set_frame_format(frame_format);
+        self
+    }
+
+    /// send a checksum behind each payload and verify the checksums of received packets.
+    /// corrupted packets fail with `packet_assembly::Error::ChecksumMismatch`. both sides have to enable it.
+    pub fn with_checksum(mut self, checksum: FrameChecksum) -> PacketConnection<S> {
+        self.packet_assembler.set_checksum(checksum);
+        self.packet_encoder.set_checksum(checksum);
+        self
+    }
+
+    /// send `len` bytes from the reader as a single packet without loading the whole payload into memory.
+    /// if the reader fails or ends early, the packet can not be completed and the stream is shut down.
+    pub fn send_stream(&mut self, reader: impl Read, len: usize) -> Result<(), Error> {
+        send_packet_stream(&mut self.stream, &self.packet_encoder, reader, len)
+    }
+
+    /// receive the next packet chunk by chunk into the writer. returns the size of the payload.
+    /// the max packet size is not enforced, payloads bigger than 4GiB require `FrameFormat::Leb128`.
+    pub fn receive_stream(&mut self, mut writer: impl Write) -> Result<usize, Error> {
+        match self.packet_assembler.receive_stream(&mut self.stream, &mut writer) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                self.stream.shutdown(Shutdown::Both)?;
+                Err(Error::PacketAssembly(e))
+            }
         }
     }
 
     /// shuts the connection down.
     pub fn shutdown(&self, how: Shutdown) -> Result<(), Error> {
-        self.tcp_stream.shutdown(how)?;
+        self.stream.shutdown(how)?;
         Ok(())
     }
 
+    /// get the underlying stream.
+    pub fn stream(&self) -> &S {
+        &self.stream
+    }
+
+    /// get the underlying stream mutably.
+    /// reading from it directly corrupts the packet framing.
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// split the connection into a sending and a receiving half that can be used from different threads.
+    /// requires a stream that supports `ByteStream::try_clone`.
+    pub fn split(self) -> Result<(PacketSender<S>, PacketReceiver<S>), Error> {
+        let send_stream = self.stream.try_clone()?;
+        Ok((
+            PacketSender::new(send_stream, self.packet_encoder),
+            PacketReceiver::new(self.stream, self.packet_assembler),
+        ))
+    }
+}
+
+impl PacketConnection<TcpStream> {
     /// get the underlying tcp stream.
     pub fn tcp_stream(&self) -> &TcpStream {
-        &self.tcp_stream
+        &self.stream
     }
 }
 
-impl Connection for PacketConnection {
+impl<S: ByteStream> Connection for PacketConnection<S> {
     type ErrorType = Error;
 
     fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
-        self.tcp_stream.write_all(&(packet.len() as u32).to_le_bytes())?; // header
-        self.tcp_stream.write_all(packet)?;
-        self.tcp_stream.flush()?;
-        Ok(())
+        send_packet(&mut self.stream, &self.packet_encoder, packet)
     }
 
     fn receive(&mut self) -> Result<Vec<u8>, Error> {
-        match self.packet_assembler.receive_packet(&mut self.tcp_stream) {
-            Ok(v) => Ok(v),
-            Err(e) => {
-                self.tcp_stream.shutdown(Shutdown::Both)?;
-                Err(Error::PacketAssembly(e))
-            }
+        receive_packet(&mut self.stream, &mut self.packet_assembler)
+    }
+
+    /// the memory of the passed buffer is reused for the packet after the next one.
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        receive_packet_into(&mut self.stream, &mut self.packet_assembler, buffer)
+    }
+}
+
+impl<S: ByteStream> TimedReceive for PacketConnection<S> {
+    /// uses the read timeout of the stream, which is reset to blocking afterwards.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        receive_packet_timeout(&mut self.stream, &mut self.packet_assembler, timeout)
+    }
+
+    /// switches the stream to non-blocking for the call and back to blocking afterwards.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        try_receive_packet(&mut self.stream, &mut self.packet_assembler)
+    }
+}
+
+impl<S: ByteStream> SplitConnection for PacketConnection<S> {
+    type Sender = PacketSender<S>;
+    type Receiver = PacketReceiver<S>;
+
+    fn split(self) -> Result<(PacketSender<S>, PacketReceiver<S>), Error> {
+        PacketConnection::split(self)
+    }
+}
+
+fn send_packet(stream: &mut impl ByteStream, encoder: &PacketEncoder, packet: &[u8]) -> Result<(), Error> {
+    let header = encoder.header(packet.len())?;
+    let trailer = encoder.trailer(packet);
+
+    // write the whole frame with as few syscalls as possible
+    let mut frame = [
+        IoSlice::new(&header),
+        IoSlice::new(packet),
+        IoSlice::new(trailer.as_ref().map_or(&[], |v| v.as_slice())),
+    ];
+    write_all_vectored(stream, &mut frame)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn send_packet_stream(stream: &mut impl ByteStream, encoder: &PacketEncoder, mut reader: impl Read, len: usize) -> Result<(), Error> {
+    stream.write_all(&encoder.header(len)?)?;
+
+    let mut checksum = encoder.checksum().running();
+    let mut chunk = vec![0_u8; std::cmp::min(len, STREAM_CHUNK_SIZE)];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = &mut chunk[..std::cmp::min(remaining, STREAM_CHUNK_SIZE)];
+        if let Err(e) = reader.read_exact(chunk) {
+            // the remote waits for the rest of the packet, so the framing is lost
+            stream.shutdown(Shutdown::Both)?;
+            return Err(Error::StreamRead(e));
+        }
+
+        checksum.update(chunk);
+        stream.write_all(chunk)?;
+        remaining -= chunk.len();
+    }
+
+    if let Some(trailer) = checksum.finish() {
+        stream.write_all(&trailer)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+fn write_all_vectored(stream: &mut impl ByteStream, mut buffers: &mut [IoSlice]) -> std::io::Result<()> {
+    IoSlice::advance_slices(&mut buffers, 0); // skip empty buffers
+    while !buffers.is_empty() {
+        match stream.write_vectored(buffers) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(size) => IoSlice::advance_slices(&mut buffers, size),
+            Err(e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn receive_packet(stream: &mut impl ByteStream, assembler: &mut PacketAssembly) -> Result<Vec<u8>, Error> {
+    match assembler.receive_packet(stream) {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            stream.shutdown(Shutdown::Both)?;
+            Err(Error::PacketAssembly(e))
+        }
+    }
+}
+
+fn receive_packet_into(stream: &mut impl ByteStream, assembler: &mut PacketAssembly, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    let packet = receive_packet(stream, assembler)?;
+    assembler.recycle(std::mem::replace(buffer, packet));
+    Ok(())
+}
+
+fn receive_packet_timeout(
+    stream: &mut impl ByteStream,
+    assembler: &mut PacketAssembly,
+    timeout: Duration,
+) -> Result<Option<Vec<u8>>, Error> {
+    let deadline = Instant::now() + timeout;
+    let result = loop {
+        match next_packet(stream, assembler) {
+            Ok(Some(packet)) => break Ok(Some(packet)),
+            Ok(None) => (),
+            Err(e) => break Err(e),
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break Ok(None);
+        }
+
+        stream.set_read_timeout(Some(remaining))?;
+        match try_receive_chunk(stream, assembler) {
+            Ok(true) => continue,
+            Ok(false) => break Ok(None),
+            Err(e) => break Err(e),
+        }
+    };
+
+    stream.set_read_timeout(None)?;
+    result
+}
+
+fn try_receive_packet(stream: &mut impl ByteStream, assembler: &mut PacketAssembly) -> Result<Option<Vec<u8>>, Error> {
+    stream.set_nonblocking(true)?;
+    let result = loop {
+        match next_packet(stream, assembler) {
+            Ok(Some(packet)) => break Ok(Some(packet)),
+            Ok(None) => (),
+            Err(e) => break Err(e),
+        }
+
+        match try_receive_chunk(stream, assembler) {
+            Ok(true) => continue,
+            Ok(false) => break Ok(None),
+            Err(e) => break Err(e),
+        }
+    };
+
+    stream.set_nonblocking(false)?;
+    result
+}
+
+/// like `try_receive_packet`, but only touches the read timeout of the stream.
+/// switching to non-blocking would also affect writes of other handles to the same stream.
+fn poll_packet(stream: &mut impl ByteStream, assembler: &mut PacketAssembly) -> Result<Option<Vec<u8>>, Error> {
+    stream.set_read_timeout(Some(MIN_READ_TIMEOUT))?;
+    let result = loop {
+        match next_packet(stream, assembler) {
+            Ok(Some(packet)) => break Ok(Some(packet)),
+            Ok(None) => (),
+            Err(e) => break Err(e),
+        }
+
+        match try_receive_chunk(stream, assembler) {
+            Ok(true) => continue,
+            Ok(false) => break Ok(None),
+            Err(e) => break Err(e),
+        }
+    };
+
+    stream.set_read_timeout(None)?;
+    result
+}
+
+fn next_packet(stream: &mut impl ByteStream, assembler: &mut PacketAssembly) -> Result<Option<Vec<u8>>, Error> {
+    match assembler.next_packet() {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            stream.shutdown(Shutdown::Both)?;
+            Err(Error::PacketAssembly(e))
+        }
+    }
+}
+
+// returns false if no data was available
+fn try_receive_chunk(stream: &mut impl ByteStream, assembler: &mut PacketAssembly) -> Result<bool, Error> {
+    match assembler.try_receive_next_packet_chunk(stream) {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            stream.shutdown(Shutdown::Both)?;
+            Err(Error::PacketAssembly(e))
         }
     }
 }
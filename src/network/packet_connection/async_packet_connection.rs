@@ -0,0 +1,118 @@
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::async_connection::AsyncConnection;
+
+use super::{
+    constants::DEFAULT_MAX_PACKET_SIZE,
+    packet_assembly,
+    packet_codec::{DecodeError, FrameChecksum, FrameFormat, PacketDecoder, PacketEncoder},
+    Error,
+};
+
+/// async version of `PacketConnection`. uses the same wire format, so both can talk to each other.
+pub struct AsyncPacketConnection<S = TcpStream> {
+    stream: S,
+    buffer: Vec<u8>,
+    packet_decoder: PacketDecoder,
+    packet_encoder: PacketEncoder,
+    // packets that were finished before the error are handed out first
+    decode_error: Option<DecodeError>,
+}
+
+impl<S> AsyncPacketConnection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    pub fn new(stream: S, receive_buffer_size: usize) -> AsyncPacketConnection<S> {
+        AsyncPacketConnection {
+            stream,
+            buffer: vec![0_u8; receive_buffer_size],
+            packet_decoder: PacketDecoder::new(DEFAULT_MAX_PACKET_SIZE),
+            packet_encoder: PacketEncoder::new(),
+            decode_error: None,
+        }
+    }
+
+    /// limit the size of packets the remote is allowed to send.
+    /// receiving a header that announces a bigger packet fails with `packet_assembly::Error::PacketTooLarge`.
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> AsyncPacketConnection<S> {
+        self.packet_decoder.set_max_packet_size(max_packet_size);
+        self
+    }
+
+    /// use a different header format. both sides have to use the same format.
+    pub fn with_frame_format(mut self, frame_format: FrameFormat) -> AsyncPacketConnection<S> {
+        self.packet_decoder.set_frame_format(frame_format);
+        self.packet_encoder.set_frame_format(frame_format);
+        self
+    }
+
+    /// send and verify a checksum behind each payload. both sides have to enable it.
+    pub fn with_checksum(mut self, checksum: FrameChecksum) -> AsyncPacketConnection<S> {
+        self.packet_decoder.set_checksum(checksum);
+        self.packet_encoder.set_checksum(checksum);
+        self
+    }
+
+    /// shuts the sending direction of the connection down.
+    pub async fn shutdown(&mut self) -> Result<(), Error> {
+        self.stream.shutdown().await?;
+        Ok(())
+    }
+
+    /// get the underlying stream.
+    pub fn stream(&self) -> &S {
+        &self.stream
+    }
+
+    async fn receive_next_packet_chunk(&mut self) -> Result<(), packet_assembly::Error> {
+        let size = self.stream.read(&mut self.buffer).await?;
+        if size == 0 {
+            return Err(packet_assembly::Error::ReceivedFin);
+        }
+
+        if let Err(e) = self.packet_decoder.push(&self.buffer[..size]) {
+            self.decode_error = Some(e);
+        }
+        Ok(())
+    }
+}
+
+impl<S> AsyncConnection for AsyncPacketConnection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    type ErrorType = Error;
+
+    async fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        let header = self.packet_encoder.header(packet.len())?;
+        self.stream.write_all(&header).await?;
+        self.stream.write_all(packet).await?;
+        if let Some(trailer) = self.packet_encoder.trailer(packet) {
+            self.stream.write_all(&trailer).await?;
+        }
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        loop {
+            if let Some(packet) = self.packet_decoder.next_packet() {
+                return Ok(packet);
+            }
+
+            let result = match self.decode_error.take() {
+                Some(e) => Err(packet_assembly::Error::from(e)),
+                None => self.receive_next_packet_chunk().await,
+            };
+
+            if let Err(e) = result {
+                self.stream.shutdown().await?;
+                return Err(Error::PacketAssembly(e));
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::{self, Cursor, ErrorKind, Read, Write},
+    net::{Shutdown, TcpStream},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+/// byte stream that packets can be sent over.
+/// everything besides reading and writing is optional and does nothing by default.
+pub trait ByteStream: Read + Write {
+    /// shuts the stream down. streams that have no notion of shutting down ignore the call.
+    fn shutdown(&self, _how: Shutdown) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// let reads fail with `WouldBlock` or `TimedOut` after the timeout. `None` blocks indefinitely.
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Err(ErrorKind::Unsupported.into())
+    }
+
+    /// let reads fail with `WouldBlock` instead of waiting for data.
+    fn set_nonblocking(&self, _nonblocking: bool) -> io::Result<()> {
+        Err(ErrorKind::Unsupported.into())
+    }
+
+    /// create a second handle to the same stream, e.g. to read and write from different threads.
+    fn try_clone(&self) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        Err(ErrorKind::Unsupported.into())
+    }
+}
+
+impl ByteStream for TcpStream {
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        TcpStream::shutdown(self, how)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+}
+
+#[cfg(unix)]
+impl ByteStream for UnixStream {
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        UnixStream::shutdown(self, how)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+}
+
+// reading from memory never blocks, so there is nothing to configure
+impl<T> ByteStream for Cursor<T>
+where
+    Cursor<T>: Read + Write,
+{
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_nonblocking(&self, _nonblocking: bool) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// combines a separate reader and writer (e.g. the stdout and stdin pipes of a child process) into one stream.
+pub struct DuplexStream<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: Read, W: Write> DuplexStream<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+
+    pub fn get_ref(&self) -> (&R, &W) {
+        (&self.reader, &self.writer)
+    }
+
+    pub fn get_mut(&mut self) -> (&mut R, &mut W) {
+        (&mut self.reader, &mut self.writer)
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<R: Read, W> Read for DuplexStream<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R, W: Write> Write for DuplexStream<R, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<R: Read, W: Write> ByteStream for DuplexStream<R, W> {}
@@ -1 +1,11 @@
-pub const HEADER_SIZE: usize = 4;
+use std::time::Duration;
+
+/// default limit for received packets, so a remote can't make us allocate gigabytes with a single header.
+/// raise it with `with_max_packet_size` if bigger packets are expected.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+
+/// size of the pieces a streamed payload is sent in.
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// shortest read timeout, used to poll a stream without blocking.
+pub const MIN_READ_TIMEOUT: Duration = Duration::from_micros(1);
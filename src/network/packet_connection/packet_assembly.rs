@@ -1,11 +1,6 @@
-use crate::data_buffer::DataBuffer;
-
-use super::{
-    constants::HEADER_SIZE,
-    packet_buffer::{PacketBuffer, PacketState},
-};
+use super::packet_codec::{DecodeError, FrameChecksum, FrameFormat, PacketDecoder, StreamChunk};
 use displaydoc::Display;
-use std::{io::Read, net::TcpStream};
+use std::io::{ErrorKind, Read, Write};
 use thiserror::Error;
 
 #[derive(Debug, Display, Error)]
@@ -14,59 +9,144 @@ pub enum Error {
     ReceivedFin,
     /// Invalid packet data
     InvalidData,
+    /// Remote announced a packet of {announced} bytes which exceeds the limit of {limit} bytes
+    PacketTooLarge { announced: usize, limit: usize },
+    /// Checksum of the received packet does not match its payload
+    ChecksumMismatch,
     /// Socket error while trying to receive data
     Receive(#[from] std::io::Error),
+    /// Failed to write the streamed packet: {0}
+    StreamWrite(std::io::Error),
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::PacketTooLarge { announced, limit } => Error::PacketTooLarge { announced, limit },
+            DecodeError::InvalidHeader => Error::InvalidData,
+            DecodeError::ChecksumMismatch => Error::ChecksumMismatch,
+            DecodeError::StreamInProgress | DecodeError::StreamNotStarted => Error::InvalidData,
+        }
+    }
 }
 
-#[derive(Clone)]
+/// reads from a stream until the packet decoder has a full packet ready.
 pub struct PacketAssembly {
-    buffer: DataBuffer,
+    buffer: Vec<u8>,
+    decoder: PacketDecoder,
+    // packets that were finished before the error are handed out first
+    decode_error: Option<DecodeError>,
 }
 
 impl PacketAssembly {
-    pub fn new(buffer_size: usize) -> PacketAssembly {
+    pub fn new(buffer_size: usize, max_packet_size: usize) -> PacketAssembly {
         PacketAssembly {
-            buffer: DataBuffer::new(buffer_size),
+            buffer: vec![0_u8; buffer_size],
+            decoder: PacketDecoder::new(max_packet_size),
+            decode_error: None,
         }
     }
 
-    pub fn receive_packet(&mut self, tcp_stream: &mut TcpStream) -> Result<Vec<u8>, Error> {
-        if self.buffer.is_empty() {
-            self.receive_next_packet_chunk(tcp_stream)?;
-        }
+    pub fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.decoder.set_max_packet_size(max_packet_size);
+    }
 
-        // create a new packet
-        let packet_size = get_packet_size(&mut self.buffer)?;
-        let mut packet = PacketBuffer::new(packet_size);
+    pub fn set_frame_format(&mut self, frame_format: FrameFormat) {
+        self.decoder.set_frame_format(frame_format);
+    }
 
+    pub fn set_checksum(&mut self, checksum: FrameChecksum) {
+        self.decoder.set_checksum(checksum);
+    }
+
+    pub fn receive_packet(&mut self, stream: &mut impl Read) -> Result<Vec<u8>, Error> {
         loop {
-            match packet.fill(&mut self.buffer) {
-                PacketState::Finished => return Ok(packet.into_vec()),
-                PacketState::RequiresData => self.receive_next_packet_chunk(tcp_stream)?,
+            if let Some(packet) = self.next_packet()? {
+                return Ok(packet);
             }
+
+            self.receive_next_packet_chunk(stream)?;
         }
     }
 
-    fn receive_next_packet_chunk(&mut self, tcp_stream: &mut TcpStream) -> Result<(), Error> {
-        self.buffer.refill(|buffer| {
-            let size = tcp_stream.read(buffer)?;
+    /// receive the next packet chunk by chunk into the writer instead of assembling it in memory.
+    /// returns the payload size. if the checksum does not match, the written data is corrupted.
+    pub fn receive_stream(&mut self, stream: &mut impl Read, writer: &mut impl Write) -> Result<usize, Error> {
+        if let Some(packet) = self.next_packet()? {
+            writer.write_all(&packet).map_err(Error::StreamWrite)?;
+            let payload_size = packet.len();
+            self.recycle(packet);
+            return Ok(payload_size);
+        }
+
+        let buffered = self.decoder.start_stream();
+        writer.write_all(&buffered).map_err(Error::StreamWrite)?;
+        let mut payload_size = buffered.len();
+        self.recycle(buffered);
+
+        loop {
+            let size = stream.read(&mut self.buffer)?;
             if size == 0 {
                 return Err(Error::ReceivedFin);
             }
-            Ok(size)
-        })?;
 
-        Ok(())
+            let mut data = &self.buffer[..size];
+            loop {
+                match self.decoder.next_stream_chunk(&mut data)? {
+                    StreamChunk::Payload(chunk) => {
+                        writer.write_all(chunk).map_err(Error::StreamWrite)?;
+                        payload_size += chunk.len();
+                    }
+                    StreamChunk::RequiresData => break,
+                    StreamChunk::Finished => {
+                        // data behind the streamed packet belongs to the following packets
+                        if let Err(e) = self.decoder.push(data) {
+                            self.decode_error = Some(e);
+                        }
+                        return Ok(payload_size);
+                    }
+                }
+            }
+        }
     }
-}
 
-/// read the first 4 bytes of the buffer to determine the packet size
-fn get_packet_size(data: &mut DataBuffer) -> Result<usize, Error> {
-    let header = data.take(HEADER_SIZE);
-    if header.len() < HEADER_SIZE {
-        return Err(Error::InvalidData);
+    /// take a packet that was already fully received.
+    /// fails once all packets before a decoding error have been taken.
+    pub fn next_packet(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        match self.decoder.next_packet() {
+            Some(packet) => Ok(Some(packet)),
+            None => match self.decode_error.take() {
+                Some(e) => Err(e.into()),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// reuse the memory of a packet that is not needed anymore for the next packet.
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        self.decoder.recycle(buffer);
     }
 
-    let size = u32::from_le_bytes(TryInto::<[u8; 4]>::try_into(header).unwrap());
-    Ok(size as usize)
+    /// read one chunk of data from the stream. returns false if the stream timed out or would block.
+    /// partially received packets are kept, so receiving can resume with the next call.
+    pub fn try_receive_next_packet_chunk(&mut self, stream: &mut impl Read) -> Result<bool, Error> {
+        match self.receive_next_packet_chunk(stream) {
+            Ok(()) => Ok(true),
+            Err(Error::Receive(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(false),
+            Err(Error::Receive(e)) if e.kind() == ErrorKind::Interrupted => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn receive_next_packet_chunk(&mut self, stream: &mut impl Read) -> Result<(), Error> {
+        let size = stream.read(&mut self.buffer)?;
+        if size == 0 {
+            return Err(Error::ReceivedFin);
+        }
+
+        if let Err(e) = self.decoder.push(&self.buffer[..size]) {
+            self.decode_error = Some(e);
+        }
+        Ok(())
+    }
 }
@@ -1,4 +1,6 @@
-use crate::data_buffer::DataBuffer;
+/// upper limit for the memory that is reserved up front for a packet.
+/// bigger packets grow their buffer while the data is actually arriving.
+const MAX_INITIAL_CAPACITY: usize = 64 * 1024;
 
 pub enum PacketState {
     Finished,
@@ -7,47 +9,34 @@ pub enum PacketState {
 
 pub struct PacketBuffer {
     buffer: Vec<u8>,
-    current_pos: usize,
+    packet_size: usize,
 }
 
 impl PacketBuffer {
-    pub fn new(capacity: usize) -> Self {
-        Self {
-            buffer: vec![0_u8; capacity],
-            current_pos: 0,
-        }
+    /// reuse the memory of an existing buffer. its content is discarded.
+    pub fn with_buffer(packet_size: usize, mut buffer: Vec<u8>) -> Self {
+        buffer.clear();
+        buffer.reserve(std::cmp::min(packet_size, MAX_INITIAL_CAPACITY));
+        Self { buffer, packet_size }
     }
 
     pub fn into_vec(self) -> Vec<u8> {
         self.buffer
     }
 
-    fn packet_size(&self) -> usize {
-        self.buffer.len()
-    }
-
-    fn remaining_space(&self) -> usize {
-        self.packet_size() - self.current_pos
+    pub fn remaining_space(&self) -> usize {
+        self.packet_size - self.buffer.len()
     }
 
-    pub fn fill(&mut self, data: &mut DataBuffer) -> PacketState {
-        if self.remaining_space() == 0 {
-            return PacketState::Finished;
-        }
+    /// moves as much data into the packet as it still needs. the consumed data is cut off the front of the slice.
+    pub fn fill(&mut self, data: &mut &[u8]) -> PacketState {
+        let count = std::cmp::min(self.remaining_space(), data.len());
+        let (packet_data, rest) = data.split_at(count);
+        self.buffer.extend_from_slice(packet_data);
+        *data = rest;
 
-        let remaining_space = self.remaining_space();
-        if data.remaining() > remaining_space {
-            // since too much data is availabe for the current packet, fill it up and keep unused data in the buffer
-            self.buffer.clone_from_slice(data.take(remaining_space));
-        } else {
-            // all the data fits in the current packet
-            let remaining_data = data.take_to_end();
-            self.buffer[self.current_pos..self.current_pos + remaining_data.len()].clone_from_slice(remaining_data);
-            self.current_pos += remaining_data.len();
-
-            if self.remaining_space() > 0 {
-                return PacketState::RequiresData;
-            }
+        if self.remaining_space() > 0 {
+            return PacketState::RequiresData;
         }
 
         PacketState::Finished
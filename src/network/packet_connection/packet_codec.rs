@@ -0,0 +1,536 @@
+use std::{collections::VecDeque, ops::Deref};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use super::packet_buffer::{PacketBuffer, PacketState};
+
+/// longest header of all frame formats (a LEB128 encoded u64).
+const MAX_HEADER_SIZE: usize = 10;
+
+/// size of the checksum trailer behind the payload.
+pub const CHECKSUM_SIZE: usize = 4;
+
+#[derive(Debug, Display, Error)]
+pub enum DecodeError {
+    /// Remote announced a packet of {announced} bytes which exceeds the limit of {limit} bytes
+    PacketTooLarge { announced: usize, limit: usize },
+    /// Header does not contain a valid packet size
+    InvalidHeader,
+    /// Checksum of the received packet does not match its payload
+    ChecksumMismatch,
+    /// Packet is still being streamed and can not be buffered
+    StreamInProgress,
+    /// Partially buffered packet was not switched to streaming
+    StreamNotStarted,
+}
+
+#[derive(Debug, Display, Error)]
+pub enum EncodeError {
+    /// Payload of {size} bytes exceeds the limit of {limit} bytes
+    PayloadTooLarge { size: usize, limit: usize },
+}
+
+/// how the payload size is written in front of each packet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// 4 byte little endian u32, the format used by default.
+    #[default]
+    U32LittleEndian,
+    /// 4 byte big endian u32.
+    U32BigEndian,
+    /// 2 byte little endian u16.
+    U16LittleEndian,
+    /// 2 byte big endian u16.
+    U16BigEndian,
+    /// LEB128 variable length integer with 1 to 10 bytes.
+    Leb128,
+}
+
+impl FrameFormat {
+    /// biggest payload size the header is able to express.
+    pub fn max_payload_size(&self) -> usize {
+        match self {
+            FrameFormat::U32LittleEndian | FrameFormat::U32BigEndian => u32::MAX as usize,
+            FrameFormat::U16LittleEndian | FrameFormat::U16BigEndian => u16::MAX as usize,
+            FrameFormat::Leb128 => usize::MAX,
+        }
+    }
+
+    // fixed size formats know their header size up front
+    fn fixed_header_size(&self) -> Option<usize> {
+        match self {
+            FrameFormat::U32LittleEndian | FrameFormat::U32BigEndian => Some(4),
+            FrameFormat::U16LittleEndian | FrameFormat::U16BigEndian => Some(2),
+            FrameFormat::Leb128 => None,
+        }
+    }
+}
+
+/// optional integrity check that is sent behind each payload.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameChecksum {
+    /// no trailer, the format used by default.
+    #[default]
+    None,
+    /// 4 byte little endian CRC32C of the payload.
+    Crc32c,
+}
+
+impl FrameChecksum {
+    /// calculate the trailer for the passed payload. returns `None` if no checksum is used.
+    pub fn trailer(&self, payload: &[u8]) -> Option<[u8; CHECKSUM_SIZE]> {
+        let mut checksum = self.running();
+        checksum.update(payload);
+        checksum.finish()
+    }
+
+    /// start a checksum over a payload that is only available in pieces.
+    pub fn running(&self) -> RunningChecksum {
+        RunningChecksum { checksum: *self, crc: 0 }
+    }
+}
+
+/// checksum that is calculated piece by piece, e.g. while a payload is streamed.
+#[derive(Clone, Copy, Debug)]
+pub struct RunningChecksum {
+    checksum: FrameChecksum,
+    crc: u32,
+}
+
+impl RunningChecksum {
+    /// add the next piece of the payload.
+    pub fn update(&mut self, data: &[u8]) {
+        if self.checksum == FrameChecksum::Crc32c {
+            self.crc = crc32c::crc32c_append(self.crc, data);
+        }
+    }
+
+    /// get the trailer for all pieces added so far. returns `None` if no checksum is used.
+    pub fn finish(&self) -> Option<[u8; CHECKSUM_SIZE]> {
+        match self.checksum {
+            FrameChecksum::None => None,
+            FrameChecksum::Crc32c => Some(self.crc.to_le_bytes()),
+        }
+    }
+}
+
+/// result of `PacketDecoder::next_stream_chunk`.
+pub enum StreamChunk<'a> {
+    /// the next piece of the streamed payload.
+    Payload(&'a [u8]),
+    /// all passed data was consumed, the packet is not finished yet.
+    RequiresData,
+    /// the streamed packet is complete and its checksum, if enabled, was verified.
+    Finished,
+}
+
+/// encoded size header of a single packet.
+pub struct FrameHeader {
+    bytes: [u8; MAX_HEADER_SIZE],
+    len: usize,
+}
+
+impl FrameHeader {
+    fn set(&mut self, bytes: &[u8]) {
+        self.bytes[..bytes.len()].copy_from_slice(bytes);
+        self.len = bytes.len();
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.len] = byte;
+        self.len += 1;
+    }
+}
+
+impl Deref for FrameHeader {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// produces the wire format of packets: a header with the payload size followed by the payload
+/// and, if enabled, a checksum trailer.
+#[derive(Clone, Default)]
+pub struct PacketEncoder {
+    frame_format: FrameFormat,
+    checksum: FrameChecksum,
+}
+
+impl PacketEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_frame_format(frame_format: FrameFormat) -> Self {
+        Self {
+            frame_format,
+            checksum: FrameChecksum::default(),
+        }
+    }
+
+    pub fn frame_format(&self) -> FrameFormat {
+        self.frame_format
+    }
+
+    pub fn set_frame_format(&mut self, frame_format: FrameFormat) {
+        self.frame_format = frame_format;
+    }
+
+    pub fn checksum(&self) -> FrameChecksum {
+        self.checksum
+    }
+
+    pub fn set_checksum(&mut self, checksum: FrameChecksum) {
+        self.checksum = checksum;
+    }
+
+    /// get the trailer that has to be sent behind the payload, if checksums are enabled.
+    pub fn trailer(&self, payload: &[u8]) -> Option<[u8; CHECKSUM_SIZE]> {
+        self.checksum.trailer(payload)
+    }
+
+    /// get the header that has to be sent in front of a payload with the passed size.
+    pub fn header(&self, payload_size: usize) -> Result<FrameHeader, EncodeError> {
+        let limit = self.frame_format.max_payload_size();
+        if payload_size > limit {
+            return Err(EncodeError::PayloadTooLarge {
+                size: payload_size,
+                limit,
+            });
+        }
+
+        let mut header = FrameHeader {
+            bytes: [0; MAX_HEADER_SIZE],
+            len: 0,
+        };
+
+        match self.frame_format {
+            FrameFormat::U32LittleEndian => header.set(&(payload_size as u32).to_le_bytes()),
+            FrameFormat::U32BigEndian => header.set(&(payload_size as u32).to_be_bytes()),
+            FrameFormat::U16LittleEndian => header.set(&(payload_size as u16).to_le_bytes()),
+            FrameFormat::U16BigEndian => header.set(&(payload_size as u16).to_be_bytes()),
+            FrameFormat::Leb128 => {
+                let mut value = payload_size as u64;
+                loop {
+                    let byte = (value & 0x7f) as u8;
+                    value >>= 7;
+                    if value == 0 {
+                        header.push(byte);
+                        break;
+                    }
+                    header.push(byte | 0x80);
+                }
+            }
+        }
+
+        Ok(header)
+    }
+
+    /// append the whole frame to the passed buffer.
+    pub fn encode(&self, payload: &[u8], destination: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let header = self.header(payload.len())?;
+        destination.reserve(header.len() + payload.len() + CHECKSUM_SIZE);
+        destination.extend_from_slice(&header);
+        destination.extend_from_slice(payload);
+        if let Some(trailer) = self.trailer(payload) {
+            destination.extend_from_slice(&trailer);
+        }
+        Ok(())
+    }
+}
+
+/// state machine that turns arbitrarily chunked bytes back into packets without doing any IO itself.
+///
+/// # Examples
+///
+/// ```
+/// let mut decoder = PacketDecoder::new(1024);
+/// decoder.push(&[3, 0, 0])?;
+/// assert!(decoder.next_packet().is_none());
+/// decoder.push(&[0, 1, 2, 3])?;
+/// assert_eq!(decoder.next_packet(), Some(vec![1, 2, 3]));
+/// ```
+pub struct PacketDecoder {
+    frame_format: FrameFormat,
+    checksum: FrameChecksum,
+    state: DecodeState,
+    header: [u8; MAX_HEADER_SIZE],
+    header_len: usize,
+    trailer: [u8; CHECKSUM_SIZE],
+    trailer_len: usize,
+    finished_packets: VecDeque<Vec<u8>>,
+    spare_buffer: Option<Vec<u8>>,
+    max_packet_size: usize,
+}
+
+enum DecodeState {
+    Header,
+    Payload(PacketBuffer),
+    Trailer(Vec<u8>),
+    Stream { remaining: usize, checksum: RunningChecksum },
+}
+
+impl PacketDecoder {
+    pub fn new(max_packet_size: usize) -> Self {
+        Self::with_frame_format(FrameFormat::default(), max_packet_size)
+    }
+
+    pub fn with_frame_format(frame_format: FrameFormat, max_packet_size: usize) -> Self {
+        Self {
+            frame_format,
+            checksum: FrameChecksum::default(),
+            state: DecodeState::Header,
+            header: [0; MAX_HEADER_SIZE],
+            header_len: 0,
+            trailer: [0; CHECKSUM_SIZE],
+            trailer_len: 0,
+            finished_packets: VecDeque::new(),
+            spare_buffer: None,
+            max_packet_size,
+        }
+    }
+
+    pub fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.max_packet_size = max_packet_size;
+    }
+
+    /// change the header format. only switch formats before any data was pushed.
+    pub fn set_frame_format(&mut self, frame_format: FrameFormat) {
+        self.frame_format = frame_format;
+    }
+
+    /// expect a checksum trailer behind each payload. only switch before any data was pushed.
+    pub fn set_checksum(&mut self, checksum: FrameChecksum) {
+        self.checksum = checksum;
+    }
+
+    /// feed received data into the decoder. finished packets are queued up until they are taken via `next_packet`.
+    /// after an error the framing is out of sync and the decoder should be discarded.
+    pub fn push(&mut self, mut data: &[u8]) -> Result<(), DecodeError> {
+        loop {
+            match &mut self.state {
+                DecodeState::Header => match self.fill_header(&mut data)? {
+                    Some(packet_size) => self.state = DecodeState::Payload(self.start_packet(packet_size)?),
+                    None => break,
+                },
+                DecodeState::Payload(packet) => match packet.fill(&mut data) {
+                    PacketState::Finished => self.finish_payload(),
+                    PacketState::RequiresData => break,
+                },
+                DecodeState::Trailer(_) => {
+                    if !self.fill_trailer(&mut data) {
+                        break;
+                    }
+                    self.finish_trailer()?;
+                }
+                DecodeState::Stream { .. } => return Err(DecodeError::StreamInProgress),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// hand out the payload of the next unfinished packet piece by piece instead of buffering it.
+    /// returns the part of the payload that was already buffered, which comes before all chunks.
+    /// finished packets have to be taken via `next_packet` first.
+    pub fn start_stream(&mut self) -> Vec<u8> {
+        let (payload, remaining) = match std::mem::replace(&mut self.state, DecodeState::Header) {
+            DecodeState::Header => return Vec::new(),
+            DecodeState::Payload(packet) => {
+                let remaining = packet.remaining_space();
+                (packet.into_vec(), remaining)
+            }
+            DecodeState::Trailer(packet) => (packet, 0),
+            state @ DecodeState::Stream { .. } => {
+                self.state = state;
+                return Vec::new();
+            }
+        };
+
+        let mut checksum = self.checksum.running();
+        checksum.update(&payload);
+        self.state = DecodeState::Stream { remaining, checksum };
+        payload
+    }
+
+    /// take the next piece of the streamed payload from the front of the passed data.
+    /// a partially buffered packet has to be switched over with `start_stream` first.
+    /// the max packet size does not apply, since the payload is never held in memory.
+    /// data behind a finished packet is left in the slice and can be pushed as usual.
+    pub fn next_stream_chunk<'a>(&mut self, data: &mut &'a [u8]) -> Result<StreamChunk<'a>, DecodeError> {
+        loop {
+            match &mut self.state {
+                DecodeState::Header => match self.fill_header(data)? {
+                    Some(packet_size) => {
+                        self.state = DecodeState::Stream {
+                            remaining: packet_size,
+                            checksum: self.checksum.running(),
+                        }
+                    }
+                    None => return Ok(StreamChunk::RequiresData),
+                },
+                DecodeState::Stream { remaining: 0, checksum } => {
+                    let expected = match checksum.finish() {
+                        Some(v) => v,
+                        None => {
+                            self.state = DecodeState::Header;
+                            return Ok(StreamChunk::Finished);
+                        }
+                    };
+                    if !self.fill_trailer(data) {
+                        return Ok(StreamChunk::RequiresData);
+                    }
+
+                    self.trailer_len = 0;
+                    self.state = DecodeState::Header;
+                    if self.trailer != expected {
+                        return Err(DecodeError::ChecksumMismatch);
+                    }
+                    return Ok(StreamChunk::Finished);
+                }
+                DecodeState::Stream { remaining, checksum } => {
+                    if data.is_empty() {
+                        return Ok(StreamChunk::RequiresData);
+                    }
+
+                    let count = std::cmp::min(*remaining, data.len());
+                    let (chunk, rest) = data.split_at(count);
+                    *remaining -= count;
+                    checksum.update(chunk);
+                    *data = rest;
+                    return Ok(StreamChunk::Payload(chunk));
+                }
+                DecodeState::Payload(_) | DecodeState::Trailer(_) => return Err(DecodeError::StreamNotStarted),
+            }
+        }
+    }
+
+    /// take the oldest finished packet.
+    pub fn next_packet(&mut self) -> Option<Vec<u8>> {
+        self.finished_packets.pop_front()
+    }
+
+    /// hand a packet buffer that is not needed anymore back to the decoder.
+    /// the next packet is received into its memory instead of allocating a new buffer.
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        self.spare_buffer = Some(buffer);
+    }
+
+    /// true if the decoder holds bytes of a packet that is not finished yet.
+    pub fn has_partial_packet(&self) -> bool {
+        self.header_len > 0 || !matches!(self.state, DecodeState::Header)
+    }
+
+    // returns the packet size once the header is complete
+    fn fill_header(&mut self, data: &mut &[u8]) -> Result<Option<usize>, DecodeError> {
+        match self.frame_format.fixed_header_size() {
+            Some(header_size) => {
+                let count = std::cmp::min(header_size - self.header_len, data.len());
+                let (header_data, rest) = data.split_at(count);
+                self.header[self.header_len..self.header_len + count].copy_from_slice(header_data);
+                self.header_len += count;
+                *data = rest;
+
+                if self.header_len < header_size {
+                    return Ok(None);
+                }
+            }
+            None => loop {
+                let (&byte, rest) = match data.split_first() {
+                    Some(v) => v,
+                    None => return Ok(None),
+                };
+                *data = rest;
+
+                self.header[self.header_len] = byte;
+                self.header_len += 1;
+
+                if byte & 0x80 == 0 {
+                    break;
+                }
+                if self.header_len == MAX_HEADER_SIZE {
+                    return Err(DecodeError::InvalidHeader);
+                }
+            },
+        }
+
+        let header = &self.header[..self.header_len];
+        self.header_len = 0;
+
+        let packet_size = match self.frame_format {
+            FrameFormat::U32LittleEndian => u32::from_le_bytes(header.try_into().unwrap()) as usize,
+            FrameFormat::U32BigEndian => u32::from_be_bytes(header.try_into().unwrap()) as usize,
+            FrameFormat::U16LittleEndian => u16::from_le_bytes(header.try_into().unwrap()) as usize,
+            FrameFormat::U16BigEndian => u16::from_be_bytes(header.try_into().unwrap()) as usize,
+            FrameFormat::Leb128 => decode_leb128(header)?,
+        };
+
+        Ok(Some(packet_size))
+    }
+
+    fn start_packet(&mut self, packet_size: usize) -> Result<PacketBuffer, DecodeError> {
+        if packet_size > self.max_packet_size {
+            return Err(DecodeError::PacketTooLarge {
+                announced: packet_size,
+                limit: self.max_packet_size,
+            });
+        }
+
+        Ok(PacketBuffer::with_buffer(packet_size, self.spare_buffer.take().unwrap_or_default()))
+    }
+
+    fn finish_payload(&mut self) {
+        let packet = match std::mem::replace(&mut self.state, DecodeState::Header) {
+            DecodeState::Payload(packet) => packet.into_vec(),
+            _ => return,
+        };
+
+        match self.checksum {
+            FrameChecksum::None => self.finished_packets.push_back(packet),
+            FrameChecksum::Crc32c => self.state = DecodeState::Trailer(packet),
+        }
+    }
+
+    // returns true once the trailer is complete
+    fn fill_trailer(&mut self, data: &mut &[u8]) -> bool {
+        let count = std::cmp::min(CHECKSUM_SIZE - self.trailer_len, data.len());
+        let (trailer_data, rest) = data.split_at(count);
+        self.trailer[self.trailer_len..self.trailer_len + count].copy_from_slice(trailer_data);
+        self.trailer_len += count;
+        *data = rest;
+
+        self.trailer_len == CHECKSUM_SIZE
+    }
+
+    fn finish_trailer(&mut self) -> Result<(), DecodeError> {
+        self.trailer_len = 0;
+        let packet = match std::mem::replace(&mut self.state, DecodeState::Header) {
+            DecodeState::Trailer(packet) => packet,
+            _ => return Ok(()),
+        };
+
+        if self.checksum.trailer(&packet) != Some(self.trailer) {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        self.finished_packets.push_back(packet);
+        Ok(())
+    }
+}
+
+fn decode_leb128(header: &[u8]) -> Result<usize, DecodeError> {
+    let mut value: u64 = 0;
+    for (i, byte) in header.iter().enumerate() {
+        let bits = (byte & 0x7f) as u64;
+        // the 10th byte only has room for the highest bit of a u64
+        if i == MAX_HEADER_SIZE - 1 && bits > 1 {
+            return Err(DecodeError::InvalidHeader);
+        }
+        value |= bits << (7 * i);
+    }
+
+    usize::try_from(value).map_err(|_| DecodeError::InvalidHeader)
+}
@@ -0,0 +1,80 @@
+use std::{
+    net::{Shutdown, TcpStream},
+    time::Duration,
+};
+
+use crate::connection::{ConnectionReceiver, ConnectionSender, TimedConnectionReceiver};
+
+use super::{
+    byte_stream::ByteStream, packet_assembly::PacketAssembly, packet_codec::PacketEncoder, poll_packet, receive_packet,
+    receive_packet_into, receive_packet_timeout, send_packet, Error,
+};
+
+/// sending half of a split `PacketConnection`.
+pub struct PacketSender<S = TcpStream> {
+    stream: S,
+    packet_encoder: PacketEncoder,
+}
+
+impl<S: ByteStream> PacketSender<S> {
+    pub(super) fn new(stream: S, packet_encoder: PacketEncoder) -> Self {
+        Self { stream, packet_encoder }
+    }
+
+    /// shuts the underlying stream down. affects the receiving half as well.
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), Error> {
+        self.stream.shutdown(how)?;
+        Ok(())
+    }
+}
+
+impl<S: ByteStream> ConnectionSender for PacketSender<S> {
+    type ErrorType = Error;
+
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        send_packet(&mut self.stream, &self.packet_encoder, packet)
+    }
+}
+
+/// receiving half of a split `PacketConnection`.
+pub struct PacketReceiver<S = TcpStream> {
+    stream: S,
+    packet_assembler: PacketAssembly,
+}
+
+impl<S: ByteStream> PacketReceiver<S> {
+    pub(super) fn new(stream: S, packet_assembler: PacketAssembly) -> Self {
+        Self { stream, packet_assembler }
+    }
+
+    /// shuts the underlying stream down. affects the sending half as well.
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), Error> {
+        self.stream.shutdown(how)?;
+        Ok(())
+    }
+}
+
+impl<S: ByteStream> ConnectionReceiver for PacketReceiver<S> {
+    type ErrorType = Error;
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        receive_packet(&mut self.stream, &mut self.packet_assembler)
+    }
+
+    /// the memory of the passed buffer is reused for the packet after the next one.
+    fn receive_into(&mut self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        receive_packet_into(&mut self.stream, &mut self.packet_assembler, buffer)
+    }
+}
+
+impl<S: ByteStream> TimedConnectionReceiver for PacketReceiver<S> {
+    /// uses the read timeout of the stream, which is reset to blocking afterwards.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        receive_packet_timeout(&mut self.stream, &mut self.packet_assembler, timeout)
+    }
+
+    /// polls the stream with a minimal read timeout, so the sending half keeps blocking writes.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        poll_packet(&mut self.stream, &mut self.packet_assembler)
+    }
+}
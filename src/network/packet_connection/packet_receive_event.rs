@@ -1,7 +1,8 @@
 use std::{
     cell::RefCell,
-    net::Shutdown,
+    net::{Shutdown, TcpStream},
     sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
 
 use displaydoc::Display;
@@ -10,10 +11,11 @@ use thiserror::Error;
 use crate::{
     connection::Connection,
     events::{event::Event, subscription::Subscription, Invokable, Subscribable},
+    heartbeat_connection::{self, heartbeat::Heartbeat},
     packet_connection,
 };
 
-use super::PacketConnection;
+use super::{byte_stream::ByteStream, PacketConnection};
 
 type EventHandler = dyn Fn(&Vec<u8>) + Send + Sync;
 
@@ -23,23 +25,37 @@ pub enum Error {
     PacketConnection(#[from] packet_connection::Error),
 }
 
-pub struct PacketReceiveEvent {
-    packet_connection: RefCell<PacketConnection>,
+pub struct PacketReceiveEvent<S = TcpStream> {
+    packet_connection: RefCell<PacketConnection<S>>,
     receive_event: RefCell<Event<Vec<u8>>>,
+    heartbeat: Option<RefCell<Heartbeat>>,
     started: AtomicBool,
     stop: AtomicBool,
 }
 
-impl PacketReceiveEvent {
-    pub fn new(packet_connection: PacketConnection) -> PacketReceiveEvent {
+impl<S: ByteStream> PacketReceiveEvent<S> {
+    pub fn new(packet_connection: PacketConnection<S>) -> PacketReceiveEvent<S> {
         PacketReceiveEvent {
             packet_connection: RefCell::new(packet_connection),
             receive_event: RefCell::new(Event::new()),
+            heartbeat: None,
             started: AtomicBool::new(false),
             stop: AtomicBool::new(false),
         }
     }
 
+    /// expect the remote to use a `HeartbeatConnection` with the same settings. dead peers stop the event loop
+    /// instead of blocking it forever.
+    pub fn with_heartbeat(mut self, interval: Duration, timeout: Duration) -> PacketReceiveEvent<S> {
+        self.heartbeat = Some(RefCell::new(Heartbeat::new(interval, timeout)));
+        self
+    }
+
+    /// round trip time measured with the last answered ping, if heartbeats are enabled.
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        self.heartbeat.as_ref().and_then(|heartbeat| heartbeat.borrow().round_trip_time())
+    }
+
     pub fn start(&self) {
         // guarantee only one thread can ever pass through and execute loop
         if !self.locked_start_check() {
@@ -47,14 +63,28 @@ impl PacketReceiveEvent {
         }
 
         while !self.stop.load(Ordering::SeqCst) {
-            let receive_result = self.packet_connection.borrow_mut().receive();
+            let receive_result = self.receive();
             match receive_result {
-                Ok(v) => self.receive_event.borrow_mut().invoke(&v),
-                _ => self.stop().unwrap(),
+                Ok(Some(v)) => self.receive_event.borrow_mut().invoke(&v),
+                Ok(None) => {}
+                // the connection is broken already, shutting it down may fail as well
+                Err(_) => self.stop().unwrap_or(()),
             };
         }
     }
 
+    // returns `None` if only a heartbeat frame was received
+    fn receive(&self) -> Result<Option<Vec<u8>>, heartbeat_connection::Error> {
+        let mut packet_connection = self.packet_connection.borrow_mut();
+        match &self.heartbeat {
+            Some(heartbeat) => heartbeat_connection::receive_packet(&mut *packet_connection, &mut heartbeat.borrow_mut(), None),
+            None => packet_connection
+                .receive()
+                .map(Some)
+                .map_err(|e| heartbeat_connection::Error::Connection(e.to_string())),
+        }
+    }
+
     fn locked_start_check(&self) -> bool {
         self.set_atomic_bool(&self.started)
     }
@@ -0,0 +1,111 @@
+use bytes::{BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{
+    constants::DEFAULT_MAX_PACKET_SIZE,
+    packet_assembly,
+    packet_codec::{DecodeError, FrameChecksum, FrameFormat, PacketDecoder, PacketEncoder, CHECKSUM_SIZE},
+    Error,
+};
+
+/// `tokio_util` codec that speaks the `PacketConnection` wire format, e.g. to be used with `Framed`.
+/// frames bigger than the maximum frame length are rejected in both directions.
+pub struct PacketFrameCodec {
+    packet_decoder: PacketDecoder,
+    packet_encoder: PacketEncoder,
+    max_frame_length: usize,
+    // packets that were finished before the error are handed out first
+    decode_error: Option<DecodeError>,
+}
+
+impl PacketFrameCodec {
+    pub fn new() -> Self {
+        Self::with_max_frame_length(DEFAULT_MAX_PACKET_SIZE)
+    }
+
+    pub fn with_max_frame_length(max_frame_length: usize) -> Self {
+        Self {
+            packet_decoder: PacketDecoder::new(max_frame_length),
+            packet_encoder: PacketEncoder::new(),
+            max_frame_length,
+            decode_error: None,
+        }
+    }
+
+    /// use a different header format. both sides have to use the same format.
+    pub fn with_frame_format(mut self, frame_format: FrameFormat) -> Self {
+        self.packet_decoder.set_frame_format(frame_format);
+        self.packet_encoder.set_frame_format(frame_format);
+        self
+    }
+
+    /// send and verify a checksum behind each payload. both sides have to enable it.
+    pub fn with_checksum(mut self, checksum: FrameChecksum) -> Self {
+        self.packet_decoder.set_checksum(checksum);
+        self.packet_encoder.set_checksum(checksum);
+        self
+    }
+
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+}
+
+impl Default for PacketFrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for PacketFrameCodec {
+    type Item = Vec<u8>;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, Error> {
+        // the packet decoder keeps partial packets itself, so all available data can be consumed right away
+        if !src.is_empty() && self.decode_error.is_none() {
+            if let Err(e) = self.packet_decoder.push(src) {
+                self.decode_error = Some(e);
+            }
+            src.clear();
+        }
+
+        match self.packet_decoder.next_packet() {
+            Some(packet) => Ok(Some(packet)),
+            None => match self.decode_error.take() {
+                Some(e) => Err(Error::PacketAssembly(packet_assembly::Error::from(e))),
+                None => Ok(None),
+            },
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, Error> {
+        match self.decode(src)? {
+            Some(packet) => Ok(Some(packet)),
+            None if self.packet_decoder.has_partial_packet() => Err(Error::PacketAssembly(packet_assembly::Error::ReceivedFin)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<&[u8]> for PacketFrameCodec {
+    type Error = Error;
+
+    fn encode(&mut self, packet: &[u8], dst: &mut BytesMut) -> Result<(), Error> {
+        if packet.len() > self.max_frame_length {
+            return Err(Error::PayloadTooLarge {
+                size: packet.len(),
+                limit: self.max_frame_length,
+            });
+        }
+
+        let header = self.packet_encoder.header(packet.len())?;
+        dst.reserve(header.len() + packet.len() + CHECKSUM_SIZE);
+        dst.put_slice(&header);
+        dst.put_slice(packet);
+        if let Some(trailer) = self.packet_encoder.trailer(packet) {
+            dst.put_slice(&trailer);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,350 @@
+mod constants;
+pub mod worker_pool;
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use displaydoc::Display;
+use generic_array::ArrayLength;
+use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::{
+    encrypted_connection::EncryptedConnection,
+    encryption::Encryption,
+    events::{
+        subscription::{Subscription, SubscriptionStorage},
+        EventHandler,
+    },
+    key_exchange::{HandshakeMode, KeyExchange},
+    packet_connection::PacketConnection,
+};
+
+use constants::{ACCEPT_POLL_INTERVAL, DEFAULT_HANDSHAKE_TIMEOUT, DEFAULT_MAX_CONNECTIONS, DEFAULT_WORKERS, RECEIVE_BUFFER_SIZE};
+use worker_pool::WorkerPool;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO error: {0}
+    IOError(#[from] std::io::Error),
+    /// Worker pool error: {0}
+    WorkerPool(#[from] worker_pool::Error),
+}
+
+/// what happened to a connection of a `PacketListener`, handed to its subscribers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenerEvent {
+    /// the connection is ready and handed to the session handler.
+    Opened { peer_addr: SocketAddr },
+    /// the session ended, the connection is closed.
+    Closed { peer_addr: SocketAddr },
+    /// the connection was closed right away because `max_connections` were open.
+    Rejected { peer_addr: SocketAddr },
+    HandshakeFailed { peer_addr: SocketAddr, reason: String },
+}
+
+type SetupFn<Con> = dyn Fn(TcpStream) -> Result<Con, String> + Send + Sync;
+
+/// accepts TCP connections and runs a handler for each of them on a bounded pool of worker threads.
+///
+/// a worker is busy for as long as the handler runs. to handle more sessions at the same time than there
+/// are workers, the handler can move the session to another thread.
+pub struct PacketListener<Con> {
+    listener: TcpListener,
+    setup: Arc<SetupFn<Con>>,
+    workers: usize,
+    max_connections: usize,
+    handshake_timeout: Option<Duration>,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    // clones of the streams of all open sessions, used to shut them down
+    sessions: Mutex<HashMap<u64, TcpStream>>,
+    next_session_id: AtomicU64,
+    stop: AtomicBool,
+    listener_event: Mutex<SubscriptionStorage<ListenerEvent>>,
+}
+
+/// shuts a running `PacketListener` down from another thread.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    shared: Arc<Shared>,
+}
+
+impl ShutdownHandle {
+    /// stop accepting connections and shut down all open sessions. `run` returns once all handlers are done.
+    pub fn shutdown(&self) {
+        self.shared.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// a ready connection. the remote is tracked as open until the session is dropped.
+pub struct Session<Con> {
+    connection: Con,
+    peer_addr: SocketAddr,
+    guard: SessionGuard,
+}
+
+impl<Con> Session<Con> {
+    pub fn connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// handle to shut the connection down from another thread, e.g. while the handler is blocked sending.
+    pub fn close_handle(&self) -> CloseHandle {
+        CloseHandle {
+            id: self.guard.id,
+            shared: self.guard.shared.clone(),
+        }
+    }
+}
+
+/// shuts a single session down. does nothing once the session was dropped.
+#[derive(Clone)]
+pub struct CloseHandle {
+    id: u64,
+    shared: Arc<Shared>,
+}
+
+impl CloseHandle {
+    pub fn close(&self) {
+        if let Some(stream) = self.shared.sessions.lock().get(&self.id) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+// removes the session from the listener on drop
+struct SessionGuard {
+    id: u64,
+    peer_addr: SocketAddr,
+    opened: bool,
+    shared: Arc<Shared>,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        if let Some(stream) = self.shared.sessions.lock().remove(&self.id) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        if self.opened {
+            self.shared.invoke(ListenerEvent::Closed {
+                peer_addr: self.peer_addr,
+            });
+        }
+    }
+}
+
+impl Shared {
+    // the handlers are called without holding the lock, so they are free to subscribe or unsubscribe
+    fn invoke(&self, event: ListenerEvent) {
+        let subscribers: Vec<_> = {
+            let mut listener_event = self.listener_event.lock();
+            let subscribers = listener_event.inner_mut();
+            subscribers.retain(|subscriber| subscriber.strong_count() > 0);
+            subscribers.iter().filter_map(|subscriber| subscriber.upgrade()).collect()
+        };
+        for subscriber in subscribers {
+            subscriber(&event);
+        }
+    }
+}
+
+impl PacketListener<PacketConnection> {
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, Error> {
+        Self::with_setup(address, |stream| Ok(PacketConnection::new(stream, RECEIVE_BUFFER_SIZE)))
+    }
+}
+
+impl<Enc, N> PacketListener<EncryptedConnection<Enc, PacketConnection>>
+where
+    Enc: Encryption<SecretLength = N> + Send + 'static,
+    N: ArrayLength<u8>,
+{
+    /// runs the handshake as server on a worker before a connection is handed to the handler.
+    pub fn bind_encrypted<K>(address: impl ToSocketAddrs, kex: K) -> Result<Self, Error>
+    where
+        K: KeyExchange<SecretLength = N> + Clone + Send + Sync + 'static,
+    {
+        Self::with_setup(address, move |stream| {
+            let connection = PacketConnection::new(stream, RECEIVE_BUFFER_SIZE);
+            EncryptedConnection::with_handshake(connection, kex.clone(), HandshakeMode::Server).map_err(|e| e.to_string())
+        })
+    }
+}
+
+impl<Con: Send + 'static> PacketListener<Con> {
+    /// `setup` turns an accepted stream into a ready connection. it runs on a worker.
+    pub fn with_setup(
+        address: impl ToSocketAddrs,
+        setup: impl Fn(TcpStream) -> Result<Con, String> + Send + Sync + 'static,
+    ) -> Result<Self, Error> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener,
+            setup: Arc::new(setup),
+            workers: DEFAULT_WORKERS,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            handshake_timeout: Some(DEFAULT_HANDSHAKE_TIMEOUT),
+            shared: Arc::new(Shared {
+                sessions: Mutex::new(HashMap::new()),
+                next_session_id: AtomicU64::new(0),
+                stop: AtomicBool::new(false),
+                listener_event: Mutex::new(SubscriptionStorage::new()),
+            }),
+        })
+    }
+
+    /// number of threads that perform handshakes and run the handlers.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        assert!(workers > 0, "a listener needs at least one worker");
+        self.workers = workers;
+        self
+    }
+
+    /// upper limit of open sessions, including connections that are still waiting for a worker.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// time `setup` may block reading from the stream before the connection is closed as failed handshake.
+    /// keeps silent clients from occupying the workers. `None` waits forever.
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Option<Duration>) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// notified about opened, closed and rejected connections. handlers are called from the worker threads.
+    pub fn subscribe(&mut self, subscriber: Box<EventHandler<ListenerEvent>>) -> Subscription<ListenerEvent> {
+        self.shared.listener_event.lock().add_event_handler(subscriber)
+    }
+
+    /// accept connections and hand each ready one to the handler until the listener is shut down.
+    /// returns after all open sessions were shut down and all handlers returned.
+    pub fn run(self, handler: impl Fn(Session<Con>) + Send + Sync + 'static) -> Result<(), Error> {
+        let handler = Arc::new(handler);
+        let worker_pool = WorkerPool::new(self.workers);
+
+        let result = self.accept_loop(&worker_pool, handler);
+
+        self.shared.stop.store(true, Ordering::SeqCst);
+        for stream in self.shared.sessions.lock().values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        worker_pool.join();
+        result
+    }
+
+    fn accept_loop<H>(&self, worker_pool: &WorkerPool, handler: Arc<H>) -> Result<(), Error>
+    where
+        H: Fn(Session<Con>) + Send + Sync + 'static,
+    {
+        while !self.shared.stop.load(Ordering::SeqCst) {
+            let (stream, peer_addr) = match self.listener.accept() {
+                Ok(v) => v,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) if e.kind() == ErrorKind::ConnectionAborted => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let Some(guard) = self.register(&stream, peer_addr)? else {
+                let _ = stream.shutdown(Shutdown::Both);
+                self.shared.invoke(ListenerEvent::Rejected { peer_addr });
+                continue;
+            };
+
+            let setup = self.setup.clone();
+            let handler = handler.clone();
+            let handshake_timeout = self.handshake_timeout;
+            worker_pool.execute(move || run_session(stream, guard, handshake_timeout, &*setup, &*handler))?;
+        }
+        Ok(())
+    }
+
+    // returns `None` if too many sessions are open
+    fn register(&self, stream: &TcpStream, peer_addr: SocketAddr) -> Result<Option<SessionGuard>, Error> {
+        stream.set_nonblocking(false)?;
+
+        let mut sessions = self.shared.sessions.lock();
+        if sessions.len() >= self.max_connections {
+            return Ok(None);
+        }
+        let id = self.shared.next_session_id.fetch_add(1, Ordering::SeqCst);
+        sessions.insert(id, stream.try_clone()?);
+
+        Ok(Some(SessionGuard {
+            id,
+            peer_addr,
+            opened: false,
+            shared: self.shared.clone(),
+        }))
+    }
+}
+
+fn run_session<Con>(
+    stream: TcpStream,
+    mut guard: SessionGuard,
+    handshake_timeout: Option<Duration>,
+    setup: &SetupFn<Con>,
+    handler: &impl Fn(Session<Con>),
+) {
+    // connections that were still queued when the listener was shut down are closed by the guard
+    if guard.shared.stop.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let peer_addr = guard.peer_addr;
+    if let Err(e) = stream.set_read_timeout(handshake_timeout) {
+        guard.shared.invoke(ListenerEvent::HandshakeFailed {
+            peer_addr,
+            reason: e.to_string(),
+        });
+        return;
+    }
+    match setup(stream) {
+        Ok(connection) => {
+            // the clone shares the socket with the connection, the timeout only applies to the handshake
+            if let Some(stream) = guard.shared.sessions.lock().get(&guard.id) {
+                let _ = stream.set_read_timeout(None);
+            }
+            guard.opened = true;
+            guard.shared.invoke(ListenerEvent::Opened { peer_addr });
+            handler(Session {
+                connection,
+                peer_addr,
+                guard,
+            });
+        }
+        Err(reason) => guard.shared.invoke(ListenerEvent::HandshakeFailed { peer_addr, reason }),
+    }
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// default number of threads that perform handshakes and run the session handlers.
+pub const DEFAULT_WORKERS: usize = 8;
+
+/// default upper limit of open sessions. further connections are closed right after accepting them.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+/// receive buffer size of the accepted packet connections.
+pub const RECEIVE_BUFFER_SIZE: usize = 1024;
+
+/// how often the accept loop checks whether the listener was shut down.
+pub const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// default time a client has to complete the handshake before its connection is closed.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
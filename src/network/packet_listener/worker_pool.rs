@@ -0,0 +1,69 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc},
+    thread::{self, JoinHandle},
+};
+
+use displaydoc::Display;
+use parking_lot::Mutex;
+use thiserror::Error;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// All workers of the pool have stopped
+    Stopped,
+}
+
+/// fixed number of threads that execute the queued jobs one after another.
+/// a panicking job does not take its worker down, the worker continues with the next job.
+pub struct WorkerPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "a worker pool needs at least one worker");
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..size)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || loop {
+                    // the lock is released before the job runs
+                    let job = receiver.lock().recv();
+                    match job {
+                        Ok(job) => {
+                            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                        }
+                        Err(_) => return,
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// queue a job. it runs as soon as a worker is idle.
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) -> Result<(), Error> {
+        match &self.sender {
+            Some(sender) => sender.send(Box::new(job)).map_err(|_| Error::Stopped),
+            None => Err(Error::Stopped),
+        }
+    }
+
+    /// wait until all queued jobs are done and stop the workers.
+    pub fn join(mut self) {
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,33 @@
+mod constants;
+pub mod hub;
+pub mod hub_client;
+pub mod hub_message;
+pub mod send_queue;
+
+use std::fmt::Display;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Received an invalid hub message
+    InvalidMessage,
+    /// Topic of {len} bytes exceeds the limit of 65535 bytes
+    TopicTooLong { len: usize },
+    /// Client did not read fast enough and its send queue overflowed
+    SlowConsumer,
+}
+
+fn check_topic(topic: &str) -> Result<(), Error> {
+    match topic.len() > u16::MAX as usize {
+        true => Err(Error::TopicTooLong { len: topic.len() }),
+        false => Ok(()),
+    }
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
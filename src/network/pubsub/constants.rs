@@ -0,0 +1,7 @@
+use std::time::Duration;
+
+/// default number of packets that are queued for a client before the slow consumer policy applies.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// how long a client session waits for incoming messages before it sends queued packets.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(5);
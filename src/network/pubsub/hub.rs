@@ -0,0 +1,231 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    connection::TimedReceive,
+    packet_listener::{self, CloseHandle, PacketListener},
+};
+
+use super::{
+    check_topic, connection_error,
+    constants::{DEFAULT_QUEUE_CAPACITY, POLL_INTERVAL},
+    hub_message::HubMessage,
+    send_queue::{Push, SendQueue, SlowConsumerPolicy},
+    Error,
+};
+
+/// fans packets that are published on a topic out to all clients that subscribed to it.
+///
+/// every client has its own bounded send queue, so a slow client does not hold up the publishers
+/// or the other clients. what happens when a queue is full is decided by the `SlowConsumerPolicy`.
+#[derive(Clone)]
+pub struct Hub {
+    shared: Arc<HubShared>,
+    queue_capacity: usize,
+    policy: SlowConsumerPolicy,
+}
+
+struct HubShared {
+    topics: Mutex<HashMap<String, HashMap<u64, Arc<ClientHandle>>>>,
+    next_client_id: AtomicU64,
+    clients: AtomicUsize,
+    dropped_packets: AtomicU64,
+}
+
+struct ClientHandle {
+    queue: SendQueue,
+    close: Option<CloseHandle>,
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hub {
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(HubShared {
+                topics: Mutex::new(HashMap::new()),
+                next_client_id: AtomicU64::new(0),
+                clients: AtomicUsize::new(0),
+                dropped_packets: AtomicU64::new(0),
+            }),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            policy: SlowConsumerPolicy::DropOldest,
+        }
+    }
+
+    /// number of packets that are queued for a client before the slow consumer policy applies.
+    /// applies to clients that connect afterwards.
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        assert!(queue_capacity > 0, "a send queue needs room for at least one packet");
+        self.queue_capacity = queue_capacity;
+        self
+    }
+
+    pub fn with_slow_consumer_policy(mut self, policy: SlowConsumerPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.shared.clients.load(Ordering::SeqCst)
+    }
+
+    /// number of topics with at least one subscriber.
+    pub fn topics(&self) -> usize {
+        self.shared.topics.lock().len()
+    }
+
+    /// number of clients that subscribed to the topic.
+    pub fn subscribers(&self, topic: &str) -> usize {
+        self.shared.topics.lock().get(topic).map_or(0, |subscribers| subscribers.len())
+    }
+
+    /// number of packets that were dropped because of `SlowConsumerPolicy::DropOldest`.
+    pub fn dropped_packets(&self) -> u64 {
+        self.shared.dropped_packets.load(Ordering::SeqCst)
+    }
+
+    /// publish a packet to all subscribers of the topic. returns the number of subscribers it was queued for.
+    pub fn publish(&self, topic: &str, payload: &[u8]) -> Result<usize, Error> {
+        check_topic(topic)?;
+        let mut message = Vec::new();
+        HubMessage::Message { topic, payload }.encode(&mut message);
+        let message: Arc<[u8]> = message.into();
+
+        let topics = self.shared.topics.lock();
+        let Some(subscribers) = topics.get(topic) else {
+            return Ok(0);
+        };
+
+        let mut queued = 0;
+        for client in subscribers.values() {
+            match client.queue.push(message.clone()) {
+                Push::Queued => queued += 1,
+                Push::DroppedOldest => {
+                    self.shared.dropped_packets.fetch_add(1, Ordering::SeqCst);
+                    queued += 1;
+                }
+                // the session may be blocked sending to the client, shutting the connection down interrupts it
+                Push::Overflowed => {
+                    if let Some(close) = &client.close {
+                        close.close();
+                    }
+                }
+            }
+        }
+        Ok(queued)
+    }
+
+    /// accept the clients of the listener until it is shut down.
+    /// clients that overflow their queue with `SlowConsumerPolicy::Disconnect` are disconnected right away.
+    pub fn serve<Con, E>(&self, listener: PacketListener<Con>) -> Result<(), packet_listener::Error>
+    where
+        Con: TimedReceive<ErrorType = E> + Send + 'static,
+        E: Display,
+    {
+        let hub = self.clone();
+        listener.run(move |mut session| {
+            let close = session.close_handle();
+            // errors only end the session of this client
+            let _ = hub.run_client(session.connection(), Some(close));
+        })
+    }
+
+    /// serve a single client until it disconnects. sending to the client and receiving from it happens on the
+    /// calling thread. with `SlowConsumerPolicy::Disconnect` an overflowing client is dropped once a pending
+    /// send returned, use `serve` to interrupt it right away.
+    pub fn handle_client<Con, E>(&self, connection: &mut Con) -> Result<(), Error>
+    where
+        Con: TimedReceive<ErrorType = E>,
+        E: Display,
+    {
+        self.run_client(connection, None)
+    }
+
+    fn run_client<Con, E>(&self, connection: &mut Con, close: Option<CloseHandle>) -> Result<(), Error>
+    where
+        Con: TimedReceive<ErrorType = E>,
+        E: Display,
+    {
+        let id = self.shared.next_client_id.fetch_add(1, Ordering::SeqCst);
+        let client = Arc::new(ClientHandle {
+            queue: SendQueue::new(self.queue_capacity, self.policy),
+            close,
+        });
+        let mut subscriptions = HashSet::new();
+
+        self.shared.clients.fetch_add(1, Ordering::SeqCst);
+        let result = self.client_loop(connection, id, &client, &mut subscriptions);
+        self.shared.clients.fetch_sub(1, Ordering::SeqCst);
+
+        let mut topics = self.shared.topics.lock();
+        for topic in subscriptions {
+            if let Some(subscribers) = topics.get_mut(&topic) {
+                subscribers.remove(&id);
+                if subscribers.is_empty() {
+                    topics.remove(&topic);
+                }
+            }
+        }
+        result
+    }
+
+    fn client_loop<Con, E>(
+        &self,
+        connection: &mut Con,
+        id: u64,
+        client: &Arc<ClientHandle>,
+        subscriptions: &mut HashSet<String>,
+    ) -> Result<(), Error>
+    where
+        Con: TimedReceive<ErrorType = E>,
+        E: Display,
+    {
+        loop {
+            for packet in client.queue.take() {
+                connection.send(&packet).map_err(connection_error)?;
+            }
+            if client.queue.is_overflowed() {
+                return Err(Error::SlowConsumer);
+            }
+
+            let Some(packet) = connection.receive_timeout(POLL_INTERVAL).map_err(connection_error)? else {
+                continue;
+            };
+            match HubMessage::parse(&packet).ok_or(Error::InvalidMessage)? {
+                HubMessage::Subscribe { topic } => {
+                    let mut topics = self.shared.topics.lock();
+                    topics.entry(topic.to_string()).or_default().insert(id, client.clone());
+                    subscriptions.insert(topic.to_string());
+                }
+                HubMessage::Unsubscribe { topic } => {
+                    let mut topics = self.shared.topics.lock();
+                    if let Some(subscribers) = topics.get_mut(topic) {
+                        subscribers.remove(&id);
+                        if subscribers.is_empty() {
+                            topics.remove(topic);
+                        }
+                    }
+                    subscriptions.remove(topic);
+                }
+                HubMessage::Publish { topic, payload } => {
+                    self.publish(topic, payload)?;
+                }
+                HubMessage::Message { .. } => return Err(Error::InvalidMessage),
+            }
+        }
+    }
+}
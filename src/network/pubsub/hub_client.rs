@@ -0,0 +1,94 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::connection::{Connection, TimedReceive};
+
+use super::{check_topic, connection_error, hub_message::HubMessage, Error};
+
+/// a packet that was published on a topic the client subscribed to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopicMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+/// client side of a `Hub`.
+pub struct HubClient<Con> {
+    connection: Con,
+    send_buffer: Vec<u8>,
+}
+
+impl<Con, E> HubClient<Con>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    pub fn new(connection: Con) -> Self {
+        Self {
+            connection,
+            send_buffer: Vec::new(),
+        }
+    }
+
+    /// packets that are published on the topic afterwards are received by this client.
+    pub fn subscribe(&mut self, topic: &str) -> Result<(), Error> {
+        check_topic(topic)?;
+        self.send(HubMessage::Subscribe { topic })
+    }
+
+    pub fn unsubscribe(&mut self, topic: &str) -> Result<(), Error> {
+        check_topic(topic)?;
+        self.send(HubMessage::Unsubscribe { topic })
+    }
+
+    /// the packet is also received by this client if it subscribed to the topic.
+    pub fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<(), Error> {
+        check_topic(topic)?;
+        self.send(HubMessage::Publish { topic, payload })
+    }
+
+    pub fn receive(&mut self) -> Result<TopicMessage, Error> {
+        let packet = self.connection.receive().map_err(connection_error)?;
+        parse_message(&packet)
+    }
+
+    /// get the underlying connection.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    fn send(&mut self, message: HubMessage) -> Result<(), Error> {
+        self.send_buffer.clear();
+        message.encode(&mut self.send_buffer);
+        self.connection.send(&self.send_buffer).map_err(connection_error)
+    }
+}
+
+impl<Con, E> HubClient<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    pub fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<TopicMessage>, Error> {
+        match self.connection.receive_timeout(timeout).map_err(connection_error)? {
+            Some(packet) => parse_message(&packet).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn try_receive(&mut self) -> Result<Option<TopicMessage>, Error> {
+        match self.connection.try_receive().map_err(connection_error)? {
+            Some(packet) => parse_message(&packet).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn parse_message(packet: &[u8]) -> Result<TopicMessage, Error> {
+    match HubMessage::parse(packet) {
+        Some(HubMessage::Message { topic, payload }) => Ok(TopicMessage {
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+        }),
+        _ => Err(Error::InvalidMessage),
+    }
+}
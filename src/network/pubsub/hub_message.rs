@@ -0,0 +1,48 @@
+const SUBSCRIBE_TAG: u8 = 0;
+const UNSUBSCRIBE_TAG: u8 = 1;
+const PUBLISH_TAG: u8 = 2;
+const MESSAGE_TAG: u8 = 3;
+
+/// message that is sent between `HubClient` and `Hub`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum HubMessage<'a> {
+    Subscribe { topic: &'a str },
+    Unsubscribe { topic: &'a str },
+    /// sent by a client, fanned out to all subscribers of the topic.
+    Publish { topic: &'a str, payload: &'a [u8] },
+    /// a published packet, sent by the hub to a subscriber.
+    Message { topic: &'a str, payload: &'a [u8] },
+}
+
+impl<'a> HubMessage<'a> {
+    /// append the wire format of the message to the passed buffer.
+    /// topics have to be shorter than 64KiB.
+    pub fn encode(&self, destination: &mut Vec<u8>) {
+        let (tag, topic, payload) = match self {
+            HubMessage::Subscribe { topic } => (SUBSCRIBE_TAG, topic, &[][..]),
+            HubMessage::Unsubscribe { topic } => (UNSUBSCRIBE_TAG, topic, &[][..]),
+            HubMessage::Publish { topic, payload } => (PUBLISH_TAG, topic, *payload),
+            HubMessage::Message { topic, payload } => (MESSAGE_TAG, topic, *payload),
+        };
+        destination.push(tag);
+        destination.extend_from_slice(&(topic.len() as u16).to_le_bytes());
+        destination.extend_from_slice(topic.as_bytes());
+        destination.extend_from_slice(payload);
+    }
+
+    /// returns `None` if the data is not a valid message.
+    pub fn parse(data: &'a [u8]) -> Option<HubMessage<'a>> {
+        let (&tag, rest) = data.split_first()?;
+        let (len, rest) = rest.split_at_checked(2)?;
+        let (topic, payload) = rest.split_at_checked(u16::from_le_bytes(len.try_into().unwrap()) as usize)?;
+        let topic = std::str::from_utf8(topic).ok()?;
+
+        match tag {
+            SUBSCRIBE_TAG if payload.is_empty() => Some(HubMessage::Subscribe { topic }),
+            UNSUBSCRIBE_TAG if payload.is_empty() => Some(HubMessage::Unsubscribe { topic }),
+            PUBLISH_TAG => Some(HubMessage::Publish { topic, payload }),
+            MESSAGE_TAG => Some(HubMessage::Message { topic, payload }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use parking_lot::Mutex;
+
+/// what happens when a client does not read fast enough and its send queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlowConsumerPolicy {
+    /// drop the oldest queued packet to make room for the new one.
+    DropOldest,
+    /// disconnect the client.
+    Disconnect,
+}
+
+/// bounded queue of the packets that still have to be sent to a client.
+pub(super) struct SendQueue {
+    state: Mutex<QueueState>,
+    capacity: usize,
+    policy: SlowConsumerPolicy,
+}
+
+struct QueueState {
+    packets: VecDeque<Arc<[u8]>>,
+    overflowed: bool,
+}
+
+/// result of queueing a packet.
+pub(super) enum Push {
+    Queued,
+    /// the queue was full and the oldest packet was dropped.
+    DroppedOldest,
+    /// the queue was full and the client has to be disconnected.
+    Overflowed,
+}
+
+impl SendQueue {
+    pub fn new(capacity: usize, policy: SlowConsumerPolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                packets: VecDeque::new(),
+                overflowed: false,
+            }),
+            capacity,
+            policy,
+        }
+    }
+
+    pub fn push(&self, packet: Arc<[u8]>) -> Push {
+        let mut state = self.state.lock();
+        if state.overflowed {
+            return Push::Overflowed;
+        }
+        if state.packets.len() < self.capacity {
+            state.packets.push_back(packet);
+            return Push::Queued;
+        }
+
+        match self.policy {
+            SlowConsumerPolicy::DropOldest => {
+                state.packets.pop_front();
+                state.packets.push_back(packet);
+                Push::DroppedOldest
+            }
+            SlowConsumerPolicy::Disconnect => {
+                state.overflowed = true;
+                state.packets.clear();
+                Push::Overflowed
+            }
+        }
+    }
+
+    /// take all queued packets.
+    pub fn take(&self) -> VecDeque<Arc<[u8]>> {
+        std::mem::take(&mut self.state.lock().packets)
+    }
+
+    pub fn is_overflowed(&self) -> bool {
+        self.state.lock().overflowed
+    }
+}
@@ -0,0 +1,349 @@
+mod constants;
+mod reactor_connection;
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    mem,
+    net,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use displaydoc::Display;
+use mio::{net::TcpStream, Events, Interest, Poll, Token, Waker};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::{
+    events::{event::Event, subscription::Subscription, EventHandler, Invokable, Subscribable},
+    packet_connection::packet_codec::{EncodeError, FrameChecksum, FrameFormat, PacketDecoder, PacketEncoder},
+};
+
+use constants::{DEFAULT_MAX_PACKET_SIZE, DEFAULT_MAX_WRITE_BUFFER_SIZE, EVENT_CAPACITY, READ_BUFFER_SIZE, WAKER_TOKEN};
+use reactor_connection::{Closed, ReactorConnection};
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO error: {0}
+    IOError(#[from] std::io::Error),
+    /// Connection {0:?} is not registered
+    UnknownConnection(ConnectionId),
+    /// Payload of {size} bytes exceeds the limit of {limit} bytes
+    PayloadTooLarge { size: usize, limit: usize },
+    /// Write buffer of connection {id:?} is full, {queued} bytes are still queued
+    WriteBufferFull { id: ConnectionId, queued: usize },
+}
+
+impl From<EncodeError> for Error {
+    fn from(e: EncodeError) -> Self {
+        match e {
+            EncodeError::PayloadTooLarge { size, limit } => Error::PayloadTooLarge { size, limit },
+        }
+    }
+}
+
+/// identifies a connection that is registered at a `Reactor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConnectionId(usize);
+
+/// a connection of a `Reactor` was closed, by the remote, because of an error or via `close`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionClosed {
+    pub id: ConnectionId,
+    /// `None` if the connection was closed cleanly.
+    pub error: Option<String>,
+}
+
+enum Command {
+    Send(ConnectionId, Vec<u8>),
+    Close(ConnectionId),
+}
+
+struct Shared {
+    commands: Mutex<Vec<Command>>,
+    stop: AtomicBool,
+    waker: Waker,
+}
+
+/// drives many packet connections from a single thread with non-blocking sockets and epoll/kqueue readiness
+/// instead of a blocking thread per connection. the framing is the same as the one of `PacketConnection`.
+///
+/// completed packets are dispatched to the handlers subscribed for their connection. the handlers run on the
+/// thread that polls and should not block. to use more cores, spread the connections over several reactors.
+pub struct Reactor {
+    poll: Poll,
+    events: Events,
+    connections: HashMap<Token, ReactorConnection>,
+    next_token: usize,
+    encoder: PacketEncoder,
+    frame_format: FrameFormat,
+    checksum: FrameChecksum,
+    max_packet_size: usize,
+    max_write_buffer_size: usize,
+    closed_event: Event<ConnectionClosed>,
+    read_buffer: Vec<u8>,
+    // connections that hit the read limit, they are read again in the next turn without waiting for readiness
+    pending_reads: Vec<Token>,
+    shared: Arc<Shared>,
+}
+
+/// queues commands for a `Reactor` from other threads or from within handlers.
+#[derive(Clone)]
+pub struct ReactorHandle {
+    shared: Arc<Shared>,
+}
+
+impl ReactorHandle {
+    /// the packet is sent during the next `poll`.
+    /// the connection is closed with an error if its write buffer is full at that point.
+    pub fn send(&self, id: ConnectionId, data: &[u8]) -> Result<(), Error> {
+        self.queue(Command::Send(id, data.to_vec()))
+    }
+
+    /// close the connection after everything queued before was sent.
+    pub fn close(&self, id: ConnectionId) -> Result<(), Error> {
+        self.queue(Command::Close(id))
+    }
+
+    /// make `run` return.
+    pub fn stop(&self) -> Result<(), Error> {
+        self.shared.stop.store(true, Ordering::SeqCst);
+        self.shared.waker.wake()?;
+        Ok(())
+    }
+
+    fn queue(&self, command: Command) -> Result<(), Error> {
+        self.shared.commands.lock().push(command);
+        self.shared.waker.wake()?;
+        Ok(())
+    }
+}
+
+impl Reactor {
+    pub fn new() -> Result<Reactor, Error> {
+        let poll = Poll::new()?;
+        let waker = Waker::new(poll.registry(), WAKER_TOKEN)?;
+
+        Ok(Reactor {
+            poll,
+            events: Events::with_capacity(EVENT_CAPACITY),
+            connections: HashMap::new(),
+            next_token: 0,
+            encoder: PacketEncoder::new(),
+            frame_format: FrameFormat::default(),
+            checksum: FrameChecksum::default(),
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            max_write_buffer_size: DEFAULT_MAX_WRITE_BUFFER_SIZE,
+            closed_event: Event::new(),
+            read_buffer: vec![0; READ_BUFFER_SIZE],
+            pending_reads: Vec::new(),
+            shared: Arc::new(Shared {
+                commands: Mutex::new(Vec::new()),
+                stop: AtomicBool::new(false),
+                waker,
+            }),
+        })
+    }
+
+    /// limit the size of packets the remotes are allowed to send. applies to connections registered afterwards.
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Reactor {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    /// limit the data that is queued per connection while the remote does not read. further packets are rejected
+    /// with `Error::WriteBufferFull`, a single packet is always accepted if nothing is queued.
+    pub fn with_max_write_buffer_size(mut self, max_write_buffer_size: usize) -> Reactor {
+        self.max_write_buffer_size = max_write_buffer_size;
+        self
+    }
+
+    /// use a different header format for all connections. the remotes have to use the same format.
+    pub fn with_frame_format(mut self, frame_format: FrameFormat) -> Reactor {
+        self.frame_format = frame_format;
+        self.encoder.set_frame_format(frame_format);
+        self
+    }
+
+    /// send and verify a checksum behind each payload. the remotes have to enable it as well.
+    pub fn with_checksum(mut self, checksum: FrameChecksum) -> Reactor {
+        self.checksum = checksum;
+        self.encoder.set_checksum(checksum);
+        self
+    }
+
+    pub fn handle(&self) -> ReactorHandle {
+        ReactorHandle {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// take over a connected stream. it is switched to non-blocking mode.
+    pub fn register(&mut self, stream: net::TcpStream) -> Result<ConnectionId, Error> {
+        stream.set_nonblocking(true)?;
+        let mut stream = TcpStream::from_std(stream);
+
+        let token = Token(self.next_token);
+        self.next_token += 1;
+        self.poll.registry().register(&mut stream, token, Interest::READABLE)?;
+
+        let mut decoder = PacketDecoder::with_frame_format(self.frame_format, self.max_packet_size);
+        decoder.set_checksum(self.checksum);
+        self.connections.insert(token, ReactorConnection::new(stream, decoder));
+        Ok(ConnectionId(token.0))
+    }
+
+    /// called with every packet that is received on the connection.
+    pub fn subscribe(&mut self, id: ConnectionId, subscriber: Box<EventHandler<Vec<u8>>>) -> Result<Subscription<Vec<u8>>, Error> {
+        let connection = self.connections.get_mut(&Token(id.0)).ok_or(Error::UnknownConnection(id))?;
+        Ok(connection.receive_event.subscribe(subscriber))
+    }
+
+    /// called whenever a connection was closed. it is removed from the reactor at that point.
+    pub fn subscribe_closed(&mut self, subscriber: Box<EventHandler<ConnectionClosed>>) -> Subscription<ConnectionClosed> {
+        self.closed_event.subscribe(subscriber)
+    }
+
+    /// number of registered connections.
+    pub fn connections(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// queue the packet and write as much as the socket accepts right away.
+    /// fails with `Error::WriteBufferFull` if the remote does not read what was queued before.
+    pub fn send(&mut self, id: ConnectionId, data: &[u8]) -> Result<(), Error> {
+        let token = Token(id.0);
+        let connection = self.connections.get_mut(&token).ok_or(Error::UnknownConnection(id))?;
+        let queued = connection.write_buffer.len();
+        if queued > 0 && queued + data.len() > self.max_write_buffer_size {
+            return Err(Error::WriteBufferFull { id, queued });
+        }
+        self.encoder.encode(data, &mut connection.write_buffer)?;
+        self.flush(token);
+        Ok(())
+    }
+
+    /// close the connection right away without sending what is still queued.
+    pub fn close(&mut self, id: ConnectionId) -> Result<(), Error> {
+        let token = Token(id.0);
+        if !self.connections.contains_key(&token) {
+            return Err(Error::UnknownConnection(id));
+        }
+        self.remove(token, Closed::EndOfStream);
+        Ok(())
+    }
+
+    /// wait for readiness at most `timeout`, forever if `None`, and handle all ready connections.
+    /// returns the number of dispatched packets.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> Result<usize, Error> {
+        self.execute_commands();
+
+        let timeout = match self.pending_reads.is_empty() {
+            true => timeout,
+            false => Some(Duration::ZERO),
+        };
+        match self.poll.poll(&mut self.events, timeout) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::Interrupted => return Ok(0),
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut ready: Vec<_> = self
+            .events
+            .iter()
+            .filter(|event| event.token() != WAKER_TOKEN)
+            .map(|event| (event.token(), event.is_readable() || event.is_read_closed(), event.is_writable()))
+            .collect();
+        for token in mem::take(&mut self.pending_reads) {
+            if !ready.iter().any(|(ready_token, readable, _)| *ready_token == token && *readable) {
+                ready.push((token, true, false));
+            }
+        }
+
+        let mut dispatched = 0;
+        for (token, readable, writable) in ready {
+            if writable {
+                self.flush(token);
+            }
+            let Some(connection) = self.connections.get_mut(&token).filter(|_| readable) else {
+                continue;
+            };
+            match connection.read(&mut self.read_buffer) {
+                Ok(read) => {
+                    dispatched += read.dispatched;
+                    if !read.drained {
+                        self.pending_reads.push(token);
+                    }
+                }
+                Err(closed) => self.remove(token, closed),
+            }
+        }
+
+        self.execute_commands();
+        Ok(dispatched)
+    }
+
+    /// poll until `ReactorHandle::stop` is called.
+    pub fn run(&mut self) -> Result<(), Error> {
+        while !self.shared.stop.swap(false, Ordering::SeqCst) {
+            self.poll(None)?;
+        }
+        Ok(())
+    }
+
+    fn execute_commands(&mut self) {
+        let commands = mem::take(&mut *self.shared.commands.lock());
+        for command in commands {
+            // commands for connections that were closed in the meantime are dropped
+            let result = match command {
+                Command::Send(id, data) => self.send(id, &data),
+                Command::Close(id) => self.close_gracefully(id),
+            };
+            // nobody can be told about a full write buffer, the remote does not keep up and is dropped
+            if let Err(e @ Error::WriteBufferFull { id, .. }) = result {
+                self.remove(Token(id.0), Closed::Failed(e.to_string()));
+            }
+        }
+    }
+
+    fn close_gracefully(&mut self, id: ConnectionId) -> Result<(), Error> {
+        let token = Token(id.0);
+        let connection = self.connections.get_mut(&token).ok_or(Error::UnknownConnection(id))?;
+        connection.closing = true;
+        self.flush(token);
+        Ok(())
+    }
+
+    // closes the connection if writing fails or everything of a closing connection was written
+    fn flush(&mut self, token: Token) {
+        let Some(connection) = self.connections.get_mut(&token) else {
+            return;
+        };
+        match connection.flush(self.poll.registry(), token) {
+            Ok(()) if connection.closing && connection.write_buffer.is_empty() => self.remove(token, Closed::EndOfStream),
+            Ok(()) => {}
+            Err(closed) => self.remove(token, closed),
+        }
+    }
+
+    fn remove(&mut self, token: Token, closed: Closed) {
+        let Some(mut connection) = self.connections.remove(&token) else {
+            return;
+        };
+        let _ = self.poll.registry().deregister(&mut connection.stream);
+        let _ = connection.stream.shutdown(net::Shutdown::Both);
+
+        let error = match closed {
+            Closed::EndOfStream => None,
+            Closed::Failed(e) => Some(e),
+        };
+        self.closed_event.invoke(&ConnectionClosed {
+            id: ConnectionId(token.0),
+            error,
+        });
+    }
+}
@@ -0,0 +1,19 @@
+use mio::Token;
+
+/// token of the waker that interrupts `poll` when commands were queued via a `ReactorHandle`.
+pub const WAKER_TOKEN: Token = Token(usize::MAX);
+
+/// capacity of the readiness event buffer, more ready connections are handled in the next `poll`.
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// size of the buffer that is shared by all connections for reading from the sockets.
+pub const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// reads from a single connection per `poll` turn, so a fast remote can't starve the other connections.
+pub const MAX_READS_PER_TURN: usize = 16;
+
+/// default limit for received packets, the same as the one of `PacketConnection`.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+
+/// default limit for the data that is queued per connection because the remote does not read fast enough.
+pub const DEFAULT_MAX_WRITE_BUFFER_SIZE: usize = 64 * 1024 * 1024;
@@ -0,0 +1,109 @@
+use std::io::{ErrorKind, Read, Write};
+
+use mio::{net::TcpStream, Interest, Registry, Token};
+
+use crate::{
+    events::{event::Event, Invokable},
+    packet_connection::packet_codec::PacketDecoder,
+};
+
+use super::constants::MAX_READS_PER_TURN;
+
+/// state of a single non-blocking connection of a `Reactor`.
+pub(super) struct ReactorConnection {
+    pub stream: TcpStream,
+    pub decoder: PacketDecoder,
+    pub receive_event: Event<Vec<u8>>,
+    // encoded packets that could not be written yet
+    pub write_buffer: Vec<u8>,
+    // closed as soon as the write buffer is empty
+    pub closing: bool,
+    waiting_for_writable: bool,
+}
+
+/// outcome of reading from a connection.
+pub(super) struct ReadOutcome {
+    pub dispatched: usize,
+    /// false if the read limit was hit before the socket was drained.
+    pub drained: bool,
+}
+
+/// why a connection has to be closed.
+pub(super) enum Closed {
+    EndOfStream,
+    Failed(String),
+}
+
+impl ReactorConnection {
+    pub fn new(stream: TcpStream, decoder: PacketDecoder) -> Self {
+        Self {
+            stream,
+            decoder,
+            receive_event: Event::new(),
+            write_buffer: Vec::new(),
+            closing: false,
+            waiting_for_writable: false,
+        }
+    }
+
+    /// read what the socket has available, up to `MAX_READS_PER_TURN` times, and dispatch the completed packets.
+    pub fn read(&mut self, read_buffer: &mut [u8]) -> Result<ReadOutcome, Closed> {
+        let mut dispatched = 0;
+        for _ in 0..MAX_READS_PER_TURN {
+            match self.stream.read(read_buffer) {
+                Ok(0) => return Err(Closed::EndOfStream),
+                Ok(size) => {
+                    let result = self.decoder.push(&read_buffer[..size]);
+                    // packets that were completed before invalid data are still dispatched
+                    while let Some(packet) = self.decoder.next_packet() {
+                        self.receive_event.invoke(&packet);
+                        self.decoder.recycle(packet);
+                        dispatched += 1;
+                    }
+                    result.map_err(|e| Closed::Failed(e.to_string()))?;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok(ReadOutcome {
+                        dispatched,
+                        drained: true,
+                    })
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(Closed::Failed(e.to_string())),
+            }
+        }
+        Ok(ReadOutcome {
+            dispatched,
+            drained: false,
+        })
+    }
+
+    /// write as much of the buffered data as the socket accepts.
+    /// waits for writable readiness if something is left.
+    pub fn flush(&mut self, registry: &Registry, token: Token) -> Result<(), Closed> {
+        let mut written = 0;
+        while written < self.write_buffer.len() {
+            match self.stream.write(&self.write_buffer[written..]) {
+                Ok(0) => return Err(Closed::EndOfStream),
+                Ok(size) => written += size,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(Closed::Failed(e.to_string())),
+            }
+        }
+        self.write_buffer.drain(..written);
+
+        let waiting_for_writable = !self.write_buffer.is_empty();
+        if waiting_for_writable != self.waiting_for_writable {
+            let interest = match waiting_for_writable {
+                true => Interest::READABLE | Interest::WRITABLE,
+                false => Interest::READABLE,
+            };
+            registry
+                .reregister(&mut self.stream, token, interest)
+                .map_err(|e| Closed::Failed(e.to_string()))?;
+            self.waiting_for_writable = waiting_for_writable;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+pub mod backoff;
+mod constants;
+
+use std::{
+    fmt::Display,
+    net::{TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use generic_array::ArrayLength;
+use thiserror::Error;
+
+use crate::{
+    connection::{Connection, TimedReceive},
+    encrypted_connection::EncryptedConnection,
+    encryption::Encryption,
+    events::{event::Event, subscription::Subscription, EventHandler, Invokable, Subscribable},
+    key_exchange::{HandshakeMode, KeyExchange},
+    packet_connection::PacketConnection,
+};
+
+use backoff::Backoff;
+use constants::{DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF, RECEIVE_BUFFER_SIZE};
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Giving up after {attempts} failed connection attempts: {reason}
+    ConnectFailed { attempts: u32, reason: String },
+}
+
+/// change of the link state, handed to the subscribers of a `ReconnectingConnection`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkEvent {
+    Connected,
+    Disconnected { reason: String },
+}
+
+type ConnectFn<Con> = dyn FnMut() -> Result<Con, String> + Send;
+
+/// client connection that replaces its underlying connection when it fails.
+///
+/// the connect closure is called lazily on first use and after every failed `send` or `receive`, with an
+/// exponential backoff between failed attempts. a packet that was being sent while the link broke is sent again
+/// over the new connection, so the remote may receive it twice or not at all.
+pub struct ReconnectingConnection<Con> {
+    connect: Box<ConnectFn<Con>>,
+    connection: Option<Con>,
+    backoff: Backoff,
+    next_attempt: Option<Instant>,
+    failed_attempts: u32,
+    max_attempts: Option<u32>,
+    link_event: Event<LinkEvent>,
+}
+
+impl ReconnectingConnection<PacketConnection> {
+    /// connects to the address via TCP.
+    pub fn tcp<A: ToSocketAddrs + Send + 'static>(address: A) -> Self {
+        Self::new(move || TcpStream::connect(&address).map(|stream| PacketConnection::new(stream, RECEIVE_BUFFER_SIZE)))
+    }
+}
+
+impl<Enc, N> ReconnectingConnection<EncryptedConnection<Enc, PacketConnection>>
+where
+    Enc: Encryption<SecretLength = N> + 'static,
+    N: ArrayLength<u8>,
+{
+    /// connects to the address via TCP and runs the handshake as client on every new connection.
+    pub fn encrypted_tcp<A, K>(address: A, kex: K) -> Self
+    where
+        A: ToSocketAddrs + Send + 'static,
+        K: KeyExchange<SecretLength = N> + Clone + Send + 'static,
+    {
+        Self::new(move || {
+            let stream = TcpStream::connect(&address).map_err(|e| e.to_string())?;
+            let connection = PacketConnection::new(stream, RECEIVE_BUFFER_SIZE);
+            EncryptedConnection::with_handshake(connection, kex.clone(), HandshakeMode::Client).map_err(|e| e.to_string())
+        })
+    }
+}
+
+impl<Con, E> ReconnectingConnection<Con>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    pub fn new<F, CE>(mut connect: F) -> Self
+    where
+        F: FnMut() -> Result<Con, CE> + Send + 'static,
+        CE: Display,
+    {
+        Self {
+            connect: Box::new(move || connect().map_err(|e| e.to_string())),
+            connection: None,
+            backoff: Backoff::new(DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF),
+            next_attempt: None,
+            failed_attempts: 0,
+            max_attempts: None,
+            link_event: Event::new(),
+        }
+    }
+
+    /// delay after the first failed attempt, doubled for every further attempt up to `max`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = Backoff::new(initial, max);
+        self
+    }
+
+    /// fail with `Error::ConnectFailed` after this many consecutive failed attempts. retries forever by default.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// notified whenever a connection was established or lost.
+    pub fn subscribe(&mut self, subscriber: Box<EventHandler<LinkEvent>>) -> Subscription<LinkEvent> {
+        self.link_event.subscribe(subscriber)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// connect now instead of on first use. does nothing if already connected.
+    pub fn connect(&mut self) -> Result<(), Error> {
+        self.ensure_connected(None).map(|_| ())
+    }
+
+    /// get the current underlying connection, `None` while disconnected.
+    pub fn get_underlying_connection(&mut self) -> Option<&mut Con> {
+        self.connection.as_mut()
+    }
+
+    // returns false if the deadline passed before a connection was established
+    fn ensure_connected(&mut self, deadline: Option<Instant>) -> Result<bool, Error> {
+        while self.connection.is_none() {
+            if let Some(next_attempt) = self.next_attempt {
+                let now = Instant::now();
+                if let Some(deadline) = deadline.filter(|deadline| *deadline < next_attempt) {
+                    thread::sleep(deadline.saturating_duration_since(now));
+                    return Ok(false);
+                }
+                thread::sleep(next_attempt.saturating_duration_since(now));
+            }
+
+            match (self.connect)() {
+                Ok(connection) => {
+                    self.connection = Some(connection);
+                    self.backoff.reset();
+                    self.next_attempt = None;
+                    self.failed_attempts = 0;
+                    self.link_event.invoke(&LinkEvent::Connected);
+                }
+                Err(reason) => {
+                    self.failed_attempts += 1;
+                    if self.max_attempts.is_some_and(|max_attempts| self.failed_attempts >= max_attempts) {
+                        let attempts = std::mem::take(&mut self.failed_attempts);
+                        return Err(Error::ConnectFailed { attempts, reason });
+                    }
+                    self.next_attempt = Some(Instant::now() + self.backoff.next_delay());
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn disconnected(&mut self, reason: String) {
+        self.connection = None;
+        self.next_attempt = Some(Instant::now() + self.backoff.next_delay());
+        self.link_event.invoke(&LinkEvent::Disconnected { reason });
+    }
+
+    // runs the operation on the current connection, reconnects and retries if it fails
+    fn with_connection<T>(
+        &mut self,
+        deadline: Option<Instant>,
+        mut operation: impl FnMut(&mut Con) -> Result<T, E>,
+    ) -> Result<Option<T>, Error> {
+        loop {
+            if !self.ensure_connected(deadline)? {
+                return Ok(None);
+            }
+
+            let connection = self.connection.as_mut().expect("connection was established");
+            match operation(connection) {
+                Ok(v) => return Ok(Some(v)),
+                Err(e) => self.disconnected(e.to_string()),
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+impl<Con, E> Connection for ReconnectingConnection<Con>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = Error;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.with_connection(None, |connection| connection.send(data)).map(|_| ())
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        self.with_connection(None, |connection| connection.receive())
+            .map(|packet| packet.expect("receiving without deadline always returns a packet"))
+    }
+}
+
+impl<Con, E> TimedReceive for ReconnectingConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    /// the timeout also limits how long reconnecting may take.
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let deadline = Instant::now() + timeout;
+        let packet = self.with_connection(Some(deadline), |connection| {
+            connection.receive_timeout(deadline.saturating_duration_since(Instant::now()))
+        })?;
+        Ok(packet.flatten())
+    }
+
+    /// only attempts to reconnect if the backoff delay already passed.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let packet = self.with_connection(Some(Instant::now()), |connection| connection.try_receive())?;
+        Ok(packet.flatten())
+    }
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use rand_core::{OsRng, RngCore};
+
+/// exponential backoff with jitter, so that many clients do not reconnect to a restarted server at the same time.
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempts: u32,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max, attempts: 0 }
+    }
+
+    /// doubles with every call up to the maximum. a random half of the delay is subtracted as jitter.
+    pub fn next_delay(&mut self) -> Duration {
+        let exponential = self.initial.saturating_mul(1 << self.attempts.min(31));
+        let delay = std::cmp::min(exponential, self.max);
+        self.attempts = self.attempts.saturating_add(1);
+
+        let jitter = (OsRng.next_u32() as f64 / u32::MAX as f64) * 0.5;
+        delay.mul_f64(1.0 - jitter)
+    }
+
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
@@ -0,0 +1,10 @@
+use std::time::Duration;
+
+/// delay before the first reconnection attempt.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// upper bound of the delay between two reconnection attempts.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// receive buffer size of the packet connections created by `ReconnectingConnection::tcp`.
+pub const RECEIVE_BUFFER_SIZE: usize = 1024;
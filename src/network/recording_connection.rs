@@ -0,0 +1,129 @@
+pub mod capture;
+mod constants;
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use capture::{CaptureWriter, Direction};
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Failed to write the capture: {0}
+    Capture(#[from] io::Error),
+}
+
+/// logs every sent and received packet with its timestamp to a capture, e.g. to reproduce bugs offline with
+/// a `ReplayConnection`. only packets that were transmitted successfully are recorded.
+///
+/// the capture is buffered, call `flush` to make sure everything is written before reading it.
+pub struct RecordingConnection<Con, W: Write = BufWriter<File>> {
+    connection: Con,
+    capture: CaptureWriter<W>,
+    start: Instant,
+}
+
+impl<Con, E> RecordingConnection<Con>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+{
+    /// record to a new file at `path`, an existing file is overwritten.
+    pub fn create(connection: Con, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(connection, BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<Con, E, W> RecordingConnection<Con, W>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+    W: Write,
+{
+    pub fn new(connection: Con, writer: W) -> Result<Self, Error> {
+        Ok(Self {
+            connection,
+            capture: CaptureWriter::new(writer)?,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.capture.flush()?)
+    }
+
+    /// get the underlying connection.
+    /// packets that are transmitted via the connection directly are not recorded.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    /// flushes the capture and returns the connection and the writer.
+    pub fn into_inner(mut self) -> Result<(Con, W), Error> {
+        self.flush()?;
+        Ok((self.connection, self.capture.into_inner()))
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) -> Result<(), Error> {
+        Ok(self.capture.write(direction, self.start.elapsed(), data)?)
+    }
+
+    fn record_received(&mut self, packet: Option<Vec<u8>>) -> Result<Option<Vec<u8>>, Error> {
+        if let Some(packet) = &packet {
+            self.record(Direction::Received, packet)?;
+        }
+        Ok(packet)
+    }
+}
+
+impl<Con, E, W> Connection for RecordingConnection<Con, W>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+    W: Write,
+{
+    type ErrorType = Error;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.connection.send(data).map_err(connection_error)?;
+        self.record(Direction::Sent, data)
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        let packet = self.connection.receive().map_err(connection_error)?;
+        self.record(Direction::Received, &packet)?;
+        Ok(packet)
+    }
+}
+
+impl<Con, E, W> TimedReceive for RecordingConnection<Con, W>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+    W: Write,
+{
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let packet = self.connection.receive_timeout(timeout).map_err(connection_error)?;
+        self.record_received(packet)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let packet = self.connection.try_receive().map_err(connection_error)?;
+        self.record_received(packet)
+    }
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
@@ -0,0 +1,175 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    time::Duration,
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use super::constants::{CAPTURE_MAGIC, CAPTURE_VERSION, DIRECTION_RECEIVED, DIRECTION_SENT, MAX_VARINT_SIZE};
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Failed to access the capture: {0}
+    Io(#[from] io::Error),
+    /// Not a capture file
+    InvalidHeader,
+    /// Unsupported capture version {version}
+    UnsupportedVersion { version: u8 },
+    /// Capture contains an invalid or truncated record
+    InvalidRecord,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// a single packet of a capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureRecord {
+    pub direction: Direction,
+    /// time since the recording started.
+    pub timestamp: Duration,
+    pub data: Vec<u8>,
+}
+
+/// writes packets in the capture format:
+/// a header of magic bytes and version, then per packet the direction, the microseconds since the previous
+/// packet and the packet length as varints, followed by the packet data.
+pub struct CaptureWriter<W> {
+    writer: W,
+    last_timestamp: u64,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&CAPTURE_MAGIC)?;
+        writer.write_all(&[CAPTURE_VERSION])?;
+        Ok(Self {
+            writer,
+            last_timestamp: 0,
+        })
+    }
+
+    /// timestamps have to be written in ascending order, earlier ones are stored as the previous timestamp.
+    pub fn write(&mut self, direction: Direction, timestamp: Duration, data: &[u8]) -> io::Result<()> {
+        let timestamp = u64::try_from(timestamp.as_micros()).unwrap_or(u64::MAX);
+        let delta = timestamp.saturating_sub(self.last_timestamp);
+        self.last_timestamp = self.last_timestamp.max(timestamp);
+
+        let direction = match direction {
+            Direction::Sent => DIRECTION_SENT,
+            Direction::Received => DIRECTION_RECEIVED,
+        };
+        self.writer.write_all(&[direction])?;
+        write_varint(&mut self.writer, delta)?;
+        write_varint(&mut self.writer, data.len() as u64)?;
+        self.writer.write_all(data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// reads packets written by a `CaptureWriter`.
+pub struct CaptureReader<R> {
+    reader: R,
+    timestamp: u64,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0; CAPTURE_MAGIC.len() + 1];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => Error::InvalidHeader,
+            _ => Error::Io(e),
+        })?;
+        if header[..CAPTURE_MAGIC.len()] != CAPTURE_MAGIC {
+            return Err(Error::InvalidHeader);
+        }
+        if header[CAPTURE_MAGIC.len()] != CAPTURE_VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: header[CAPTURE_MAGIC.len()],
+            });
+        }
+
+        Ok(Self { reader, timestamp: 0 })
+    }
+
+    /// returns `None` at the end of the capture.
+    pub fn read(&mut self) -> Result<Option<CaptureRecord>, Error> {
+        let mut direction = [0];
+        if self.reader.read(&mut direction)? == 0 {
+            return Ok(None);
+        }
+        let direction = match direction[0] {
+            DIRECTION_SENT => Direction::Sent,
+            DIRECTION_RECEIVED => Direction::Received,
+            _ => return Err(Error::InvalidRecord),
+        };
+
+        self.timestamp = self.timestamp.saturating_add(read_varint(&mut self.reader)?);
+        let len = read_varint(&mut self.reader)?;
+
+        // the length is not trusted for preallocation, a corrupted capture may contain anything
+        let mut data = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(Error::InvalidRecord);
+        }
+
+        Ok(Some(CaptureRecord {
+            direction,
+            timestamp: Duration::from_micros(self.timestamp),
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    let mut buffer = [0; MAX_VARINT_SIZE];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer[len] = byte;
+            len += 1;
+            break;
+        }
+        buffer[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&buffer[..len])
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<u64, Error> {
+    let mut value = 0_u64;
+    for i in 0..MAX_VARINT_SIZE {
+        let mut byte = [0];
+        reader.read_exact(&mut byte).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => Error::InvalidRecord,
+            _ => Error::Io(e),
+        })?;
+        value |= u64::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::InvalidRecord)
+}
@@ -0,0 +1,12 @@
+/// first bytes of every capture file.
+pub const CAPTURE_MAGIC: [u8; 4] = *b"XSRC";
+
+/// version of the capture format, written after the magic bytes.
+pub const CAPTURE_VERSION: u8 = 1;
+
+/// direction byte of a record.
+pub const DIRECTION_SENT: u8 = 0;
+pub const DIRECTION_RECEIVED: u8 = 1;
+
+/// a u64 needs at most 10 bytes as LEB128 varint.
+pub const MAX_VARINT_SIZE: usize = 10;
@@ -0,0 +1,166 @@
+mod constants;
+pub mod reliability;
+pub mod rto_estimator;
+pub mod segment;
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use reliability::Reliability;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Remote did not acknowledge a segment after {retransmissions} retransmissions
+    PeerUnreachable { retransmissions: u32 },
+}
+
+/// reliable and ordered delivery on top of an unreliable datagram connection like `DatagramConnection`.
+/// lost segments are detected with selective acknowledgements and retransmitted.
+///
+/// there is no background thread: acknowledgements are processed and segments retransmitted while the
+/// connection is used. call `flush` to wait until the remote received everything.
+pub struct ReliableConnection<Con> {
+    connection: Con,
+    reliability: Reliability,
+}
+
+impl<Con, E> ReliableConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    pub fn new(connection: Con) -> Self {
+        Self {
+            connection,
+            reliability: Reliability::new(),
+        }
+    }
+
+    /// set how often a segment is retransmitted before sending fails with `Error::PeerUnreachable`.
+    pub fn with_max_retransmissions(mut self, max_retransmissions: u32) -> Self {
+        self.reliability.set_max_retransmissions(max_retransmissions);
+        self
+    }
+
+    /// current retransmission timeout, derived from the measured round trip times.
+    pub fn rto(&self) -> Duration {
+        self.reliability.rto()
+    }
+
+    /// block until all sent packets were acknowledged by the remote.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        while self.reliability.in_flight() > 0 {
+            self.process(None)?;
+        }
+        Ok(())
+    }
+
+    /// get the underlying connection.
+    /// data that is sent via the connection directly is not protected by the reliability layer.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    // receive at most one segment, waiting until the deadline or the next retransmission
+    fn process(&mut self, deadline: Option<Instant>) -> Result<(), Error> {
+        let now = Instant::now();
+        self.reliability.poll(now)?;
+        self.send_outgoing()?;
+
+        let wake_up = match (deadline, self.reliability.next_timeout()) {
+            (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+        let segment = match wake_up {
+            Some(wake_up) => self
+                .connection
+                .receive_timeout(wake_up.saturating_duration_since(now))
+                .map_err(connection_error)?,
+            None => Some(self.connection.receive().map_err(connection_error)?),
+        };
+
+        if let Some(segment) = segment {
+            self.reliability.handle_segment(&segment, Instant::now());
+        }
+        self.reliability.poll(Instant::now())?;
+        self.send_outgoing()
+    }
+
+    fn send_outgoing(&mut self) -> Result<(), Error> {
+        while let Some(segment) = self.reliability.next_outgoing() {
+            self.connection.send(&segment).map_err(connection_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Con, E> Connection for ReliableConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    type ErrorType = Error;
+
+    /// waits for acknowledgements first if too many packets are unacknowledged.
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        while !self.reliability.can_send() {
+            self.process(None)?;
+        }
+
+        self.reliability.send(data, Instant::now());
+        self.send_outgoing()
+    }
+
+    /// returns packets in the order they were sent.
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        loop {
+            if let Some(packet) = self.reliability.next_packet() {
+                return Ok(packet);
+            }
+            self.process(None)?;
+        }
+    }
+}
+
+impl<Con, E> TimedReceive for ReliableConnection<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(packet) = self.reliability.next_packet() {
+                return Ok(Some(packet));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            self.process(Some(deadline))?;
+        }
+    }
+
+    /// processes all segments that already arrived without blocking.
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        while let Some(segment) = self.connection.try_receive().map_err(connection_error)? {
+            self.reliability.handle_segment(&segment, Instant::now());
+        }
+        self.reliability.poll(Instant::now())?;
+        self.send_outgoing()?;
+
+        Ok(self.reliability.next_packet())
+    }
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
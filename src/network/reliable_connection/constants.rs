@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+/// retransmission timeout before the first round trip was measured.
+pub const INITIAL_RTO: Duration = Duration::from_millis(200);
+
+/// lower bound of the retransmission timeout. kept small since the layer targets real-time traffic.
+pub const MIN_RTO: Duration = Duration::from_millis(10);
+
+/// upper bound of the retransmission timeout, also after backing off.
+pub const MAX_RTO: Duration = Duration::from_secs(2);
+
+/// default number of retransmissions of a single segment before the remote is considered unreachable.
+pub const DEFAULT_MAX_RETRANSMISSIONS: u32 = 10;
+
+/// number of unacknowledged segments after which `send` waits for acknowledgements.
+pub const MAX_IN_FLIGHT: usize = 256;
+
+/// how far ahead of the next expected segment data is buffered. has to be at least `MAX_IN_FLIGHT`.
+pub const RECEIVE_WINDOW: u64 = 1024;
@@ -0,0 +1,209 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use super::{
+    constants::{DEFAULT_MAX_RETRANSMISSIONS, MAX_IN_FLIGHT, RECEIVE_WINDOW},
+    rto_estimator::RtoEstimator,
+    segment::Segment,
+    Error,
+};
+
+/// state machine of the reliability layer. does not do any IO itself: segments that have to be sent
+/// are queued up until they are taken via `next_outgoing`, received segments are passed to `handle_segment`.
+pub struct Reliability {
+    next_sequence: u64,
+    in_flight: BTreeMap<u64, InFlight>,
+    next_expected: u64,
+    out_of_order: BTreeMap<u64, Vec<u8>>,
+    received_packets: VecDeque<Vec<u8>>,
+    outgoing: VecDeque<Vec<u8>>,
+    rto_estimator: RtoEstimator,
+    max_retransmissions: u32,
+}
+
+struct InFlight {
+    segment: Vec<u8>,
+    sent_at: Instant,
+    retransmissions: u32,
+}
+
+impl Default for Reliability {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reliability {
+    pub fn new() -> Self {
+        Self {
+            next_sequence: 0,
+            in_flight: BTreeMap::new(),
+            next_expected: 0,
+            out_of_order: BTreeMap::new(),
+            received_packets: VecDeque::new(),
+            outgoing: VecDeque::new(),
+            rto_estimator: RtoEstimator::new(),
+            max_retransmissions: DEFAULT_MAX_RETRANSMISSIONS,
+        }
+    }
+
+    pub fn set_max_retransmissions(&mut self, max_retransmissions: u32) {
+        self.max_retransmissions = max_retransmissions;
+    }
+
+    /// current retransmission timeout.
+    pub fn rto(&self) -> Duration {
+        self.rto_estimator.rto()
+    }
+
+    /// number of sent segments that were not acknowledged yet.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// false if too many segments are unacknowledged. acknowledgements have to be received before sending more.
+    pub fn can_send(&self) -> bool {
+        self.in_flight.len() < MAX_IN_FLIGHT
+    }
+
+    /// queue a packet for sending. it is retransmitted until the remote acknowledges it.
+    pub fn send(&mut self, payload: &[u8], now: Instant) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        let mut segment = Vec::new();
+        Segment::Data {
+            sequence,
+            ack: self.next_expected,
+            payload,
+        }
+        .encode(&mut segment);
+
+        self.outgoing.push_back(segment.clone());
+        self.in_flight.insert(
+            sequence,
+            InFlight {
+                segment,
+                sent_at: now,
+                retransmissions: 0,
+            },
+        );
+    }
+
+    /// process a segment that was received from the transport. invalid segments are ignored.
+    pub fn handle_segment(&mut self, data: &[u8], now: Instant) {
+        match Segment::parse(data) {
+            Some(Segment::Data { sequence, ack, payload }) => {
+                self.acknowledge(ack, 0, now);
+                self.receive_data(sequence, payload);
+                self.queue_ack();
+            }
+            Some(Segment::Ack { ack, selective }) => self.acknowledge(ack, selective, now),
+            None => (),
+        }
+    }
+
+    /// retransmit all segments whose timeout expired.
+    /// fails if a segment exceeded the maximum number of retransmissions.
+    pub fn poll(&mut self, now: Instant) -> Result<(), Error> {
+        let rto = self.rto_estimator.rto();
+        let mut retransmitted = false;
+        for segment in self.in_flight.values_mut() {
+            if now < segment.sent_at + rto {
+                continue;
+            }
+            if segment.retransmissions >= self.max_retransmissions {
+                return Err(Error::PeerUnreachable {
+                    retransmissions: segment.retransmissions,
+                });
+            }
+
+            segment.retransmissions += 1;
+            segment.sent_at = now;
+            self.outgoing.push_back(segment.segment.clone());
+            retransmitted = true;
+        }
+
+        if retransmitted {
+            self.rto_estimator.backoff();
+        }
+        Ok(())
+    }
+
+    /// point in time at which `poll` has to be called next. `None` if nothing is waiting for acknowledgement.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let rto = self.rto_estimator.rto();
+        self.in_flight.values().map(|segment| segment.sent_at + rto).min()
+    }
+
+    /// take the next segment that has to be sent over the transport.
+    pub fn next_outgoing(&mut self) -> Option<Vec<u8>> {
+        self.outgoing.pop_front()
+    }
+
+    /// take the next packet in the order it was sent.
+    pub fn next_packet(&mut self) -> Option<Vec<u8>> {
+        self.received_packets.pop_front()
+    }
+
+    fn receive_data(&mut self, sequence: u64, payload: &[u8]) {
+        // duplicates of delivered packets are only acknowledged again
+        if sequence < self.next_expected || sequence >= self.next_expected + RECEIVE_WINDOW {
+            return;
+        }
+
+        if sequence > self.next_expected {
+            self.out_of_order.entry(sequence).or_insert_with(|| payload.to_vec());
+            return;
+        }
+
+        self.received_packets.push_back(payload.to_vec());
+        self.next_expected += 1;
+        while let Some(packet) = self.out_of_order.remove(&self.next_expected) {
+            self.received_packets.push_back(packet);
+            self.next_expected += 1;
+        }
+    }
+
+    fn queue_ack(&mut self) {
+        let mut selective = 0_u64;
+        for sequence in self.out_of_order.range(self.next_expected + 1..self.next_expected + 65).map(|(k, _)| *k) {
+            selective |= 1 << (sequence - self.next_expected - 1);
+        }
+
+        let mut segment = Vec::new();
+        Segment::Ack {
+            ack: self.next_expected,
+            selective,
+        }
+        .encode(&mut segment);
+        self.outgoing.push_back(segment);
+    }
+
+    fn acknowledge(&mut self, ack: u64, selective: u64, now: Instant) {
+        // the remote can't have received segments that were never sent, such an ack is bogus
+        if ack > self.next_sequence {
+            return;
+        }
+
+        let mut acknowledged: Vec<u64> = self.in_flight.range(..ack).map(|(k, _)| *k).collect();
+        acknowledged.extend(
+            (0..64)
+                .filter(|i| selective & (1 << i) != 0)
+                .filter_map(|i| ack.checked_add(1 + i))
+                .filter(|sequence| *sequence < self.next_sequence),
+        );
+
+        for sequence in acknowledged {
+            if let Some(segment) = self.in_flight.remove(&sequence) {
+                self.rto_estimator.reset_backoff();
+                // round trips of retransmitted segments are ambiguous
+                if segment.retransmissions == 0 {
+                    self.rto_estimator.sample(now.saturating_duration_since(segment.sent_at));
+                }
+            }
+        }
+    }
+}
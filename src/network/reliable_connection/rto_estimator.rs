@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use super::constants::{INITIAL_RTO, MAX_RTO, MIN_RTO};
+
+/// estimates the retransmission timeout from measured round trip times as described in RFC 6298.
+pub struct RtoEstimator {
+    smoothed_rtt: Option<Duration>,
+    rtt_variance: Duration,
+    rto: Duration,
+    backoff: u32,
+}
+
+impl Default for RtoEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RtoEstimator {
+    pub fn new() -> Self {
+        Self {
+            smoothed_rtt: None,
+            rtt_variance: Duration::ZERO,
+            rto: INITIAL_RTO,
+            backoff: 0,
+        }
+    }
+
+    /// current retransmission timeout, including the backoff.
+    pub fn rto(&self) -> Duration {
+        self.rto.saturating_mul(1 << self.backoff).min(MAX_RTO)
+    }
+
+    /// smoothed round trip time, `None` until the first sample.
+    pub fn smoothed_rtt(&self) -> Option<Duration> {
+        self.smoothed_rtt
+    }
+
+    /// add a round trip time measurement. only use segments that were not retransmitted (Karn's algorithm).
+    pub fn sample(&mut self, rtt: Duration) {
+        let smoothed_rtt = match self.smoothed_rtt {
+            None => {
+                self.rtt_variance = rtt / 2;
+                rtt
+            }
+            Some(smoothed_rtt) => {
+                let deviation = smoothed_rtt.abs_diff(rtt);
+                self.rtt_variance = self.rtt_variance * 3 / 4 + deviation / 4;
+                smoothed_rtt * 7 / 8 + rtt / 8
+            }
+        };
+
+        self.smoothed_rtt = Some(smoothed_rtt);
+        self.rto = (smoothed_rtt + self.rtt_variance * 4).clamp(MIN_RTO, MAX_RTO);
+    }
+
+    /// double the timeout after a retransmission.
+    pub fn backoff(&mut self) {
+        if self.rto() < MAX_RTO {
+            self.backoff += 1;
+        }
+    }
+
+    /// go back to the estimated timeout once the remote acknowledges data again.
+    /// without new samples the backoff would otherwise stick, since retransmitted segments are not measured.
+    pub fn reset_backoff(&mut self) {
+        self.backoff = 0;
+    }
+}
@@ -0,0 +1,61 @@
+const DATA_TAG: u8 = 0;
+const ACK_TAG: u8 = 1;
+
+/// size of a data segment without its payload.
+pub const DATA_HEADER_SIZE: usize = 17;
+
+/// size of an acknowledgement segment.
+pub const ACK_SIZE: usize = 17;
+
+/// unit that is sent over the datagram transport.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// payload with its sequence number. carries the cumulative acknowledgement of the sender as well.
+    Data { sequence: u64, ack: u64, payload: &'a [u8] },
+    /// all sequence numbers below `ack` were received. bit `i` of `selective` acknowledges `ack + 1 + i`.
+    Ack { ack: u64, selective: u64 },
+}
+
+impl<'a> Segment<'a> {
+    /// append the wire format of the segment to the passed buffer.
+    pub fn encode(&self, destination: &mut Vec<u8>) {
+        match self {
+            Segment::Data { sequence, ack, payload } => {
+                destination.reserve(DATA_HEADER_SIZE + payload.len());
+                destination.push(DATA_TAG);
+                destination.extend_from_slice(&sequence.to_le_bytes());
+                destination.extend_from_slice(&ack.to_le_bytes());
+                destination.extend_from_slice(payload);
+            }
+            Segment::Ack { ack, selective } => {
+                destination.reserve(ACK_SIZE);
+                destination.push(ACK_TAG);
+                destination.extend_from_slice(&ack.to_le_bytes());
+                destination.extend_from_slice(&selective.to_le_bytes());
+            }
+        }
+    }
+
+    /// returns `None` if the data is not a valid segment.
+    pub fn parse(data: &'a [u8]) -> Option<Segment<'a>> {
+        let (&tag, rest) = data.split_first()?;
+        if rest.len() < 16 {
+            return None;
+        }
+
+        let first = u64::from_le_bytes(rest[..8].try_into().unwrap());
+        let second = u64::from_le_bytes(rest[8..16].try_into().unwrap());
+        match tag {
+            DATA_TAG => Some(Segment::Data {
+                sequence: first,
+                ack: second,
+                payload: &rest[16..],
+            }),
+            ACK_TAG if rest.len() == 16 => Some(Segment::Ack {
+                ack: first,
+                selective: second,
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    connection::{Connection, TimedReceive},
+    recording_connection::capture::{self, CaptureReader, CaptureRecord, Direction},
+};
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Failed to read the capture: {0}
+    Capture(#[from] capture::Error),
+    /// All recorded packets were received already
+    EndOfCapture,
+    /// Sent packet {index} does not match the recording
+    SendMismatch { index: usize },
+    /// Sent more packets than were recorded
+    UnexpectedSend,
+}
+
+/// plays back a capture of a `RecordingConnection`: the recorded received packets are returned in order.
+/// sends are discarded unless they are verified against the recorded sends with `with_send_verification`.
+pub struct ReplayConnection {
+    received: VecDeque<CaptureRecord>,
+    sent: VecDeque<Vec<u8>>,
+    sent_count: usize,
+    verify_sends: bool,
+    original_timing: bool,
+    start: Instant,
+}
+
+impl ReplayConnection {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(BufReader::new(File::open(path).map_err(capture::Error::from)?))
+    }
+
+    /// reads the whole capture upfront.
+    pub fn new(reader: impl Read) -> Result<Self, Error> {
+        let records = CaptureReader::new(reader)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_records(records))
+    }
+
+    pub fn from_records(records: impl IntoIterator<Item = CaptureRecord>) -> Self {
+        let (received, sent): (VecDeque<_>, VecDeque<_>) =
+            records.into_iter().partition(|record| record.direction == Direction::Received);
+        Self {
+            received,
+            sent: sent.into_iter().map(|record| record.data).collect(),
+            sent_count: 0,
+            verify_sends: false,
+            original_timing: false,
+            start: Instant::now(),
+        }
+    }
+
+    /// sends fail with `Error::SendMismatch` if they differ from the recorded sends.
+    pub fn with_send_verification(mut self) -> Self {
+        self.verify_sends = true;
+        self
+    }
+
+    /// packets are not received before their recorded time, measured from now on.
+    pub fn with_original_timing(mut self) -> Self {
+        self.original_timing = true;
+        self.start = Instant::now();
+        self
+    }
+
+    /// number of recorded packets that were not received yet.
+    pub fn remaining_received(&self) -> usize {
+        self.received.len()
+    }
+
+    /// number of recorded sends that were not sent yet.
+    pub fn remaining_sent(&self) -> usize {
+        self.sent.len()
+    }
+
+    /// true if the whole capture was played back.
+    pub fn is_finished(&self) -> bool {
+        self.received.is_empty() && self.sent.is_empty()
+    }
+
+    // time at which the next packet may be received, errors if there is none
+    fn next_due(&self) -> Result<Instant, Error> {
+        let record = self.received.front().ok_or(Error::EndOfCapture)?;
+        Ok(match self.original_timing {
+            true => self.start + record.timestamp,
+            false => self.start,
+        })
+    }
+
+    fn next_packet(&mut self) -> Option<Vec<u8>> {
+        self.received.pop_front().map(|record| record.data)
+    }
+}
+
+impl Connection for ReplayConnection {
+    type ErrorType = Error;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        let index = self.sent_count;
+        self.sent_count += 1;
+        match self.sent.pop_front() {
+            Some(recorded) if self.verify_sends && recorded != data => Err(Error::SendMismatch { index }),
+            None if self.verify_sends => Err(Error::UnexpectedSend),
+            _ => Ok(()),
+        }
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>, Error> {
+        thread::sleep(self.next_due()?.saturating_duration_since(Instant::now()));
+        Ok(self.next_packet().unwrap_or_default())
+    }
+}
+
+impl TimedReceive for ReplayConnection {
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let wait = self.next_due()?.saturating_duration_since(Instant::now());
+        if wait > timeout {
+            thread::sleep(timeout);
+            return Ok(None);
+        }
+        thread::sleep(wait);
+        Ok(self.next_packet())
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        if self.next_due()? > Instant::now() {
+            return Ok(None);
+        }
+        Ok(self.next_packet())
+    }
+}
@@ -0,0 +1,55 @@
+mod constants;
+pub mod rpc_client;
+pub mod rpc_message;
+pub mod rpc_server;
+
+use std::{fmt, time::Duration};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Remote failed to handle the call: {0}
+    Remote(String),
+    /// Call did not get a response within {0:?}
+    Timeout(Duration),
+    /// Method name is longer than 65535 bytes
+    MethodNameTooLong,
+}
+
+/// identifies a method on the server, either by name or by a number that both sides agreed on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MethodId {
+    Numeric(u32),
+    Name(String),
+}
+
+impl From<u32> for MethodId {
+    fn from(id: u32) -> Self {
+        MethodId::Numeric(id)
+    }
+}
+
+impl From<&str> for MethodId {
+    fn from(name: &str) -> Self {
+        MethodId::Name(name.to_string())
+    }
+}
+
+impl From<String> for MethodId {
+    fn from(name: String) -> Self {
+        MethodId::Name(name)
+    }
+}
+
+impl fmt::Display for MethodId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MethodId::Numeric(id) => write!(f, "#{id}"),
+            MethodId::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+use std::time::Duration;
+
+/// longest time the underlying connection is blocked by a waiting thread, so others can send in between.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(5);
@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::connection::TimedReceive;
+
+use super::{constants::POLL_INTERVAL, rpc_message::RpcMessage, Error, MethodId};
+
+/// calls methods of an `RpcServer` on the other side of the connection.
+/// the client can be cloned and used from several threads, responses are matched to their calls by id.
+pub struct RpcClient<Con> {
+    shared: Arc<Shared<Con>>,
+}
+
+/// a call that was sent but whose response was not taken yet. dropping it discards the response.
+pub struct PendingCall<Con> {
+    shared: Arc<Shared<Con>>,
+    call_id: u64,
+}
+
+struct Shared<Con> {
+    connection: Mutex<Con>,
+    state: Mutex<State>,
+    state_changed: Condvar,
+}
+
+struct State {
+    next_call_id: u64,
+    // calls that wait for a response, filled once it arrives
+    pending_calls: HashMap<u64, Option<Result<Vec<u8>, Error>>>,
+    error: Option<String>,
+}
+
+impl<Con> Clone for RpcClient<Con> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<Con, E> RpcClient<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    pub fn new(connection: Con) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                connection: Mutex::new(connection),
+                state: Mutex::new(State {
+                    next_call_id: 0,
+                    pending_calls: HashMap::new(),
+                    error: None,
+                }),
+                state_changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// call a method and wait at most `timeout` for its response.
+    pub fn call(&self, method: impl Into<MethodId>, payload: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        self.start_call(method, payload)?.wait(timeout)
+    }
+
+    /// send a call without waiting for the response, so several calls can be outstanding at the same time.
+    pub fn start_call(&self, method: impl Into<MethodId>, payload: &[u8]) -> Result<PendingCall<Con>, Error> {
+        let method = method.into();
+        if matches!(&method, MethodId::Name(name) if name.len() > u16::MAX as usize) {
+            return Err(Error::MethodNameTooLong);
+        }
+
+        let call_id = {
+            let mut state = self.shared.state.lock();
+            let call_id = state.next_call_id;
+            state.next_call_id += 1;
+            state.pending_calls.insert(call_id, None);
+            call_id
+        };
+        let pending_call = PendingCall {
+            shared: self.shared.clone(),
+            call_id,
+        };
+
+        let mut data = Vec::new();
+        RpcMessage::Request {
+            call_id,
+            method,
+            payload,
+        }
+        .encode(&mut data);
+        let result = self.shared.connection.lock().send(&data);
+        result.map_err(|e| self.shared.fail(e))?;
+
+        Ok(pending_call)
+    }
+}
+
+impl<Con, E> PendingCall<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    pub fn call_id(&self) -> u64 {
+        self.call_id
+    }
+
+    /// wait at most `timeout` for the response. fails with `Error::Remote` if the server returned an error.
+    pub fn wait(self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            {
+                let mut state = self.shared.state.lock();
+                if let Some(result) = state.pending_calls.get_mut(&self.call_id).and_then(Option::take) {
+                    return result;
+                }
+                if let Some(e) = &state.error {
+                    return Err(Error::Connection(e.clone()));
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::Timeout(timeout));
+            }
+
+            // only one thread reads at a time, the others get notified about the responses it dispatched
+            match self.shared.connection.try_lock() {
+                Some(mut connection) => {
+                    let message = connection.receive_timeout(remaining.min(POLL_INTERVAL));
+                    drop(connection);
+
+                    if let Some(message) = message.map_err(|e| self.shared.fail(e))? {
+                        self.shared.dispatch(&message);
+                    }
+                }
+                None => {
+                    let mut state = self.shared.state.lock();
+                    self.shared.state_changed.wait_for(&mut state, remaining.min(POLL_INTERVAL));
+                }
+            }
+        }
+    }
+}
+
+impl<Con> Drop for PendingCall<Con> {
+    fn drop(&mut self) {
+        self.shared.state.lock().pending_calls.remove(&self.call_id);
+    }
+}
+
+impl<Con> Shared<Con> {
+    // responses of unknown or abandoned calls are ignored
+    fn dispatch(&self, data: &[u8]) {
+        let (call_id, result) = match RpcMessage::parse(data) {
+            Some(RpcMessage::Response { call_id, payload }) => (call_id, Ok(payload.to_vec())),
+            Some(RpcMessage::ErrorResponse { call_id, message }) => (call_id, Err(Error::Remote(message.to_string()))),
+            _ => return,
+        };
+
+        let mut state = self.state.lock();
+        if let Some(pending_call) = state.pending_calls.get_mut(&call_id) {
+            *pending_call = Some(result);
+        }
+        drop(state);
+        self.state_changed.notify_all();
+    }
+
+    // remember the error, so all waiting calls fail with it
+    fn fail(&self, e: impl Display) -> Error {
+        let message = e.to_string();
+        self.state.lock().error = Some(message.clone());
+        self.state_changed.notify_all();
+        Error::Connection(message)
+    }
+}
@@ -0,0 +1,101 @@
+use super::MethodId;
+
+const REQUEST_TAG: u8 = 0;
+const RESPONSE_TAG: u8 = 1;
+const ERROR_RESPONSE_TAG: u8 = 2;
+
+const NUMERIC_METHOD_TAG: u8 = 0;
+const NAMED_METHOD_TAG: u8 = 1;
+
+/// message that is sent between `RpcClient` and `RpcServer`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RpcMessage<'a> {
+    /// call of a method. the call id is used to match the response.
+    Request {
+        call_id: u64,
+        method: MethodId,
+        payload: &'a [u8],
+    },
+    /// successful result of a call.
+    Response { call_id: u64, payload: &'a [u8] },
+    /// the call failed on the server.
+    ErrorResponse { call_id: u64, message: &'a str },
+}
+
+impl<'a> RpcMessage<'a> {
+    /// append the wire format of the message to the passed buffer.
+    pub fn encode(&self, destination: &mut Vec<u8>) {
+        match self {
+            RpcMessage::Request {
+                call_id,
+                method,
+                payload,
+            } => {
+                destination.push(REQUEST_TAG);
+                destination.extend_from_slice(&call_id.to_le_bytes());
+                match method {
+                    MethodId::Numeric(id) => {
+                        destination.push(NUMERIC_METHOD_TAG);
+                        destination.extend_from_slice(&id.to_le_bytes());
+                    }
+                    MethodId::Name(name) => {
+                        destination.push(NAMED_METHOD_TAG);
+                        destination.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                        destination.extend_from_slice(name.as_bytes());
+                    }
+                }
+                destination.extend_from_slice(payload);
+            }
+            RpcMessage::Response { call_id, payload } => {
+                destination.push(RESPONSE_TAG);
+                destination.extend_from_slice(&call_id.to_le_bytes());
+                destination.extend_from_slice(payload);
+            }
+            RpcMessage::ErrorResponse { call_id, message } => {
+                destination.push(ERROR_RESPONSE_TAG);
+                destination.extend_from_slice(&call_id.to_le_bytes());
+                destination.extend_from_slice(message.as_bytes());
+            }
+        }
+    }
+
+    /// returns `None` if the data is not a valid message.
+    pub fn parse(data: &'a [u8]) -> Option<RpcMessage<'a>> {
+        let (&tag, rest) = data.split_first()?;
+        let (call_id, rest) = rest.split_at_checked(8)?;
+        let call_id = u64::from_le_bytes(call_id.try_into().unwrap());
+
+        match tag {
+            REQUEST_TAG => {
+                let (method, payload) = parse_method(rest)?;
+                Some(RpcMessage::Request {
+                    call_id,
+                    method,
+                    payload,
+                })
+            }
+            RESPONSE_TAG => Some(RpcMessage::Response { call_id, payload: rest }),
+            ERROR_RESPONSE_TAG => Some(RpcMessage::ErrorResponse {
+                call_id,
+                message: std::str::from_utf8(rest).ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn parse_method(data: &[u8]) -> Option<(MethodId, &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    match tag {
+        NUMERIC_METHOD_TAG => {
+            let (id, payload) = rest.split_at_checked(4)?;
+            Some((MethodId::Numeric(u32::from_le_bytes(id.try_into().unwrap())), payload))
+        }
+        NAMED_METHOD_TAG => {
+            let (len, rest) = rest.split_at_checked(2)?;
+            let (name, payload) = rest.split_at_checked(u16::from_le_bytes(len.try_into().unwrap()) as usize)?;
+            Some((MethodId::Name(std::str::from_utf8(name).ok()?.to_string()), payload))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    connection::TimedReceive,
+    events::{event::Event, subscription::Subscription, EventHandler, Invokable, Subscribable},
+};
+
+use super::{constants::POLL_INTERVAL, rpc_message::RpcMessage, Error, MethodId};
+
+/// handles the calls of a method. the error message is sent back to the client.
+pub type MethodHandler = dyn Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync;
+
+/// an incoming call, handed to the subscribers of the server.
+#[derive(Clone, Debug)]
+pub struct RpcCall {
+    pub call_id: u64,
+    pub method: MethodId,
+    pub payload: Vec<u8>,
+}
+
+/// answers the calls of an `RpcClient` on the other side of the connection with the registered methods.
+pub struct RpcServer<Con> {
+    connection: Con,
+    methods: HashMap<MethodId, Box<MethodHandler>>,
+    call_event: Event<RpcCall>,
+    stop: Arc<AtomicBool>,
+}
+
+/// stops a running `RpcServer` from another thread.
+#[derive(Clone)]
+pub struct StopHandle {
+    stop: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+impl<Con, E> RpcServer<Con>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+{
+    pub fn new(connection: Con) -> Self {
+        Self {
+            connection,
+            methods: HashMap::new(),
+            call_event: Event::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// handle calls of the method. replaces a handler that was registered for the same method before.
+    pub fn register(
+        &mut self,
+        method: impl Into<MethodId>,
+        handler: impl Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync + 'static,
+    ) {
+        self.methods.insert(method.into(), Box::new(handler));
+    }
+
+    /// get notified about every incoming call before it is handled, e.g. for logging.
+    pub fn subscribe(&mut self, subscriber: Box<EventHandler<RpcCall>>) -> Subscription<RpcCall> {
+        self.call_event.subscribe(subscriber)
+    }
+
+    /// get a handle that stops `run` from another thread.
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle { stop: self.stop.clone() }
+    }
+
+    /// handle calls until it gets stopped or the connection fails.
+    pub fn run(&mut self) -> Result<(), Error> {
+        while !self.stop.load(Ordering::SeqCst) {
+            self.handle_next_timeout(POLL_INTERVAL)?;
+        }
+        Ok(())
+    }
+
+    /// wait for the next call and answer it.
+    pub fn handle_next(&mut self) -> Result<(), Error> {
+        let message = self.connection.receive().map_err(connection_error)?;
+        self.handle_message(&message)
+    }
+
+    /// wait at most `timeout` for the next call and answer it. returns false if no call arrived in time.
+    pub fn handle_next_timeout(&mut self, timeout: Duration) -> Result<bool, Error> {
+        match self.connection.receive_timeout(timeout).map_err(connection_error)? {
+            Some(message) => self.handle_message(&message).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// get the underlying connection.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    // messages that are not requests are ignored
+    fn handle_message(&mut self, data: &[u8]) -> Result<(), Error> {
+        let (call_id, method, payload) = match RpcMessage::parse(data) {
+            Some(RpcMessage::Request {
+                call_id,
+                method,
+                payload,
+            }) => (call_id, method, payload),
+            _ => return Ok(()),
+        };
+
+        self.call_event.invoke(&RpcCall {
+            call_id,
+            method: method.clone(),
+            payload: payload.to_vec(),
+        });
+
+        let result = match self.methods.get(&method) {
+            Some(handler) => handler(payload),
+            None => Err(format!("Unknown method {method}")),
+        };
+
+        let mut response = Vec::new();
+        match &result {
+            Ok(payload) => RpcMessage::Response { call_id, payload }.encode(&mut response),
+            Err(message) => RpcMessage::ErrorResponse { call_id, message }.encode(&mut response),
+        }
+        self.connection.send(&response).map_err(connection_error)
+    }
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
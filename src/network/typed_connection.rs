@@ -0,0 +1,103 @@
+pub mod codec;
+
+use std::{fmt::Display, marker::PhantomData, time::Duration};
+
+use displaydoc::Display;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use crate::connection::{Connection, TimedReceive};
+
+use codec::Codec;
+
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// Underlying connection error: {0}
+    Connection(String),
+    /// Failed to encode message: {0}
+    Encode(String),
+    /// Failed to decode message: {0}
+    Decode(String),
+}
+
+/// sends and receives serde types instead of raw bytes. the codec decides about the format on the wire,
+/// both sides have to use the same codec and message type.
+pub struct TypedConnection<Con, Msg, Cod> {
+    connection: Con,
+    codec: Cod,
+    send_buffer: Vec<u8>,
+    receive_buffer: Vec<u8>,
+    message_type: PhantomData<fn(Msg) -> Msg>,
+}
+
+impl<Con, Msg, Cod, E> TypedConnection<Con, Msg, Cod>
+where
+    Con: Connection<ErrorType = E>,
+    E: Display,
+    Msg: Serialize + DeserializeOwned,
+    Cod: Codec,
+{
+    pub fn new(connection: Con, codec: Cod) -> Self {
+        Self {
+            connection,
+            codec,
+            send_buffer: Vec::new(),
+            receive_buffer: Vec::new(),
+            message_type: PhantomData,
+        }
+    }
+
+    pub fn send(&mut self, message: &Msg) -> Result<(), Error> {
+        self.codec
+            .encode(message, &mut self.send_buffer)
+            .map_err(|e| Error::Encode(e.to_string()))?;
+        self.connection.send(&self.send_buffer).map_err(connection_error)
+    }
+
+    /// a packet that can not be decoded fails with `Error::Decode`. the connection stays usable.
+    pub fn receive(&mut self) -> Result<Msg, Error> {
+        self.connection.receive_into(&mut self.receive_buffer).map_err(connection_error)?;
+        self.decode(&self.receive_buffer)
+    }
+
+    /// get the underlying connection.
+    pub fn get_underlying_connection(&mut self) -> &mut Con {
+        &mut self.connection
+    }
+
+    pub fn into_inner(self) -> Con {
+        self.connection
+    }
+
+    fn decode(&self, packet: &[u8]) -> Result<Msg, Error> {
+        self.codec.decode(packet).map_err(|e| Error::Decode(e.to_string()))
+    }
+}
+
+impl<Con, Msg, Cod, E> TypedConnection<Con, Msg, Cod>
+where
+    Con: TimedReceive<ErrorType = E>,
+    E: Display,
+    Msg: Serialize + DeserializeOwned,
+    Cod: Codec,
+{
+    /// wait at most `timeout` for a message. returns `None` if no message arrived in time.
+    pub fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Msg>, Error> {
+        match self.connection.receive_timeout(timeout).map_err(connection_error)? {
+            Some(packet) => self.decode(&packet).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// receive a message if one is available without blocking.
+    pub fn try_receive(&mut self) -> Result<Option<Msg>, Error> {
+        match self.connection.try_receive().map_err(connection_error)? {
+            Some(packet) => self.decode(&packet).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn connection_error(e: impl Display) -> Error {
+    Error::Connection(e.to_string())
+}
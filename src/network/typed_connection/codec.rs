@@ -0,0 +1,79 @@
+use std::fmt::Display;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// turns serde types into bytes and back.
+pub trait Codec {
+    type Error: Display;
+
+    /// serialize the value into the passed buffer, replacing its content.
+    fn encode<T: Serialize>(&self, value: &T, destination: &mut Vec<u8>) -> Result<(), Self::Error>;
+    fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, Self::Error>;
+}
+
+/// compact binary format of the `bincode` crate.
+#[cfg(feature = "bincode")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BincodeCodec;
+
+#[cfg(feature = "bincode")]
+impl Codec for BincodeCodec {
+    type Error = bincode::Error;
+
+    fn encode<T: Serialize>(&self, value: &T, destination: &mut Vec<u8>) -> Result<(), bincode::Error> {
+        destination.clear();
+        bincode::serialize_into(destination, value)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, bincode::Error> {
+        bincode::deserialize(data)
+    }
+}
+
+/// human readable JSON, e.g. to talk to peers that are not written in rust.
+#[cfg(feature = "json")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+#[cfg(feature = "json")]
+impl Codec for JsonCodec {
+    type Error = serde_json::Error;
+
+    fn encode<T: Serialize>(&self, value: &T, destination: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+        destination.clear();
+        serde_json::to_writer(destination, value)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(data)
+    }
+}
+
+/// MessagePack, a compact binary format with implementations in many languages.
+/// structs are encoded as maps, so fields can be added without breaking older peers.
+#[cfg(feature = "msgpack")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MessagePackCodec;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePackCodec {
+    type Error = MessagePackError;
+
+    fn encode<T: Serialize>(&self, value: &T, destination: &mut Vec<u8>) -> Result<(), MessagePackError> {
+        destination.clear();
+        rmp_serde::encode::write_named(destination, value).map_err(MessagePackError::Encode)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, MessagePackError> {
+        rmp_serde::from_slice(data).map_err(MessagePackError::Decode)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub enum MessagePackError {
+    /// {0}
+    Encode(rmp_serde::encode::Error),
+    /// {0}
+    Decode(rmp_serde::decode::Error),
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "tokio")]
+
+use std::{thread, time::Duration};
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+use xs_rust_library::{
+    async_connection::AsyncConnection,
+    connection::Connection,
+    cryptography::{
+        encryption::aes256_crypto::Aes256Crypto,
+        key_exchange::{curve25519::Curve25519, HandshakeMode},
+    },
+    encrypted_connection::EncryptedConnection,
+    packet_connection::{async_packet_connection::AsyncPacketConnection, PacketConnection},
+};
+
+#[tokio::test]
+async fn async_packet_connection() {
+    let listener = TcpListener::bind("127.0.0.1:7006").await.unwrap();
+
+    let join_handle = tokio::spawn(async move {
+        let remote_stream = TcpStream::connect("127.0.0.1:7006").await.unwrap();
+        let mut remote_con = AsyncPacketConnection::new(remote_stream, 1024);
+        remote_con.send(b"test123").await.unwrap();
+        remote_con.send(&[5_u8; 1024 * 1024]).await.unwrap();
+    });
+
+    let mut local_con = AsyncPacketConnection::new(listener.accept().await.unwrap().0, 1024);
+    assert_eq!(local_con.receive().await.unwrap(), b"test123");
+    assert_eq!(local_con.receive().await.unwrap(), [5_u8; 1024 * 1024]);
+
+    join_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn blocking_interop() {
+    let listener = TcpListener::bind("127.0.0.1:7007").await.unwrap();
+
+    let join_handle = thread::spawn(|| {
+        let mut remote_con = PacketConnection::new(std::net::TcpStream::connect("127.0.0.1:7007").unwrap(), 1024);
+        let packet = remote_con.receive().unwrap();
+        remote_con.send(&packet).unwrap();
+    });
+
+    let mut local_con = AsyncPacketConnection::new(listener.accept().await.unwrap().0, 1024);
+    local_con.send(b"echo").await.unwrap();
+    assert_eq!(local_con.receive().await.unwrap(), b"echo");
+
+    join_handle.join().unwrap();
+}
+
+#[tokio::test]
+async fn async_encrypted_connection() {
+    let listener = TcpListener::bind("127.0.0.1:7008").await.unwrap();
+
+    let join_handle = tokio::spawn(async move {
+        let remote_con = AsyncPacketConnection::new(TcpStream::connect("127.0.0.1:7008").await.unwrap(), 1024);
+        let mut enc_con = EncryptedConnection::<Aes256Crypto, _>::with_handshake_async(remote_con, Curve25519, HandshakeMode::Client)
+            .await
+            .unwrap();
+        enc_con.send(b"top secret").await.unwrap();
+    });
+
+    let local_con = AsyncPacketConnection::new(listener.accept().await.unwrap().0, 1024);
+    let mut enc_con = EncryptedConnection::<Aes256Crypto, _>::with_handshake_async(local_con, Curve25519, HandshakeMode::Server)
+        .await
+        .unwrap();
+    assert_eq!(enc_con.receive().await.unwrap(), b"top secret");
+
+    join_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn cancelled_receive_keeps_partial_packet() {
+    let listener = TcpListener::bind("127.0.0.1:7009").await.unwrap();
+    let mut remote_stream = TcpStream::connect("127.0.0.1:7009").await.unwrap();
+    let mut local_con = AsyncPacketConnection::new(listener.accept().await.unwrap().0, 1024);
+
+    remote_stream.write_all(&[3, 0, 0, 0, 1]).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(50), local_con.receive()).await.is_err());
+
+    remote_stream.write_all(&[2, 3]).await.unwrap();
+    assert_eq!(local_con.receive().await.unwrap(), [1, 2, 3]);
+}
@@ -0,0 +1,119 @@
+use std::{thread, time::Duration};
+
+use xs_rust_library::{
+    chaos_connection::{chaos_config::ChaosConfig, ChaosConnection, ChaosStats},
+    connection::{Connection, TimedReceive},
+    encrypted_connection::{EncryptedConnection, TransmissionError},
+    encryption::aes256_crypto::Aes256Crypto,
+    key_exchange::{curve25519::Curve25519, HandshakeMode},
+    memory_connection::MemoryConnection,
+};
+
+fn send_numbered(connection: &mut impl Connection<ErrorType = impl std::fmt::Debug>, count: u8) {
+    for i in 0..count {
+        connection.send(&[i; 16]).unwrap();
+    }
+}
+
+fn receive_all(connection: &mut MemoryConnection) -> Vec<Vec<u8>> {
+    let mut packets = Vec::new();
+    while let Some(packet) = connection.try_receive().unwrap() {
+        packets.push(packet);
+    }
+    packets
+}
+
+#[test]
+fn same_seed_same_faults() {
+    let config = ChaosConfig::new(42)
+        .with_drop_probability(0.2)
+        .with_duplicate_probability(0.2)
+        .with_reorder_probability(0.2)
+        .with_truncate_probability(0.2)
+        .with_bit_flip_probability(0.2);
+
+    let mut runs = Vec::new();
+    for _ in 0..2 {
+        let (local, mut remote) = MemoryConnection::pair();
+        let mut local = ChaosConnection::new(local, config.clone());
+        send_numbered(&mut local, 100);
+        local.flush().unwrap();
+        runs.push((receive_all(&mut remote), local.stats()));
+    }
+
+    assert_eq!(runs[0], runs[1]);
+    let stats = runs[0].1;
+    assert_eq!(stats.sent, 100);
+    assert!(stats.dropped > 0 && stats.duplicated > 0 && stats.reordered > 0);
+    assert!(stats.truncated > 0 && stats.bit_flipped > 0);
+}
+
+#[test]
+fn drop_duplicate_and_reorder() {
+    let (local, mut remote) = MemoryConnection::pair();
+    let mut local = ChaosConnection::new(local, ChaosConfig::new(1).with_drop_probability(1.0));
+    send_numbered(&mut local, 3);
+    assert!(remote.try_receive().unwrap().is_none());
+    assert_eq!(local.stats().dropped, 3);
+
+    let (local, mut remote) = MemoryConnection::pair();
+    let mut local = ChaosConnection::new(local, ChaosConfig::new(1).with_duplicate_probability(1.0));
+    send_numbered(&mut local, 2);
+    assert_eq!(receive_all(&mut remote), [[0; 16], [0; 16], [1; 16], [1; 16]]);
+
+    let (local, mut remote) = MemoryConnection::pair();
+    let mut local = ChaosConnection::new(local, ChaosConfig::new(1).with_reorder_probability(1.0));
+    send_numbered(&mut local, 3);
+    assert_eq!(receive_all(&mut remote), [[1; 16], [0; 16]]);
+    local.flush().unwrap();
+    assert_eq!(receive_all(&mut remote), [[2; 16]]);
+    assert_eq!(
+        local.stats(),
+        ChaosStats {
+            sent: 3,
+            reordered: 2,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn delay_and_skipped_packets() {
+    let (local, mut remote) = MemoryConnection::pair();
+    let config = ChaosConfig::new(7)
+        .with_skipped_packets(1)
+        .with_delay(Duration::from_millis(20), Duration::from_millis(30))
+        .with_truncate_probability(1.0);
+    let mut local = ChaosConnection::new(local, config);
+
+    let start = std::time::Instant::now();
+    local.send(b"untouched").unwrap();
+    assert!(start.elapsed() < Duration::from_millis(20));
+    assert_eq!(remote.receive().unwrap(), b"untouched");
+
+    local.send(b"truncated").unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(remote.receive().unwrap().len() < b"truncated".len());
+}
+
+#[test]
+fn bit_flip_breaks_decryption() {
+    let (local, remote) = MemoryConnection::pair();
+    let config = ChaosConfig::new(3).with_skipped_packets(1).with_bit_flip_probability(1.0);
+
+    let join_handle = thread::spawn(move || {
+        let remote = ChaosConnection::new(remote, config);
+        let mut remote = EncryptedConnection::<Aes256Crypto, _>::with_handshake(remote, Curve25519, HandshakeMode::Client).unwrap();
+        remote.send(b"top secret").unwrap();
+    });
+
+    let mut local = EncryptedConnection::<Aes256Crypto, _>::with_handshake(local, Curve25519, HandshakeMode::Server).unwrap();
+    assert!(matches!(local.receive(), Err(TransmissionError::DecryptMessage(_))));
+    join_handle.join().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "probability 1.5 is not between 0 and 1")]
+fn invalid_probability() {
+    ChaosConfig::new(0).with_drop_probability(1.5);
+}
@@ -0,0 +1,133 @@
+#![cfg(any(feature = "zstd", feature = "lz4"))]
+
+mod util;
+
+use std::time::Duration;
+
+use util::test_connections::{new_aes_encrypted_connection_test_pair, ChannelConnection};
+use xs_rust_library::{
+    compressed_connection::{compression::Compression, CompressedConnection, Error},
+    connection::{Connection, TimedReceive},
+};
+
+fn compressions() -> Vec<Compression> {
+    vec![
+        #[cfg(feature = "zstd")]
+        Compression::zstd(),
+        #[cfg(feature = "lz4")]
+        Compression::Lz4,
+    ]
+}
+
+fn compressible_data() -> Vec<u8> {
+    b"sensor=42;temperature=21.5;humidity=40;".repeat(100)
+}
+
+fn random_data(size: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip() {
+    for compression in compressions() {
+        let (local, remote) = ChannelConnection::new_test_pair();
+        let mut local_con = CompressedConnection::new(local, compression);
+        let mut remote_con = CompressedConnection::new(remote, compression);
+
+        let packets = [Vec::new(), b"small".to_vec(), compressible_data(), random_data(4096)];
+        for packet in &packets {
+            local_con.send(packet).unwrap();
+        }
+
+        assert_eq!(&remote_con.receive().unwrap(), &packets[0]);
+        let mut buffer = Vec::new();
+        remote_con.receive_into(&mut buffer).unwrap();
+        assert_eq!(buffer, packets[1]);
+        assert_eq!(&remote_con.receive_timeout(Duration::from_secs(1)).unwrap().unwrap(), &packets[2]);
+        assert_eq!(&remote_con.try_receive().unwrap().unwrap(), &packets[3]);
+        assert!(remote_con.try_receive().unwrap().is_none());
+    }
+}
+
+#[test]
+fn threshold() {
+    for compression in compressions() {
+        let (local, mut remote) = ChannelConnection::new_test_pair();
+        let mut local_con = CompressedConnection::new(local, compression).with_threshold(1024);
+
+        let below_threshold = compressible_data()[..1000].to_vec();
+        local_con.send(&below_threshold).unwrap();
+        assert_eq!(remote.receive().unwrap().len(), below_threshold.len() + 1);
+
+        local_con.send(&compressible_data()).unwrap();
+        assert!(remote.receive().unwrap().len() < compressible_data().len() / 4);
+    }
+}
+
+#[test]
+fn incompressible_data() {
+    for compression in compressions() {
+        let (local, mut remote) = ChannelConnection::new_test_pair();
+        let mut local_con = CompressedConnection::new(local, compression);
+
+        local_con.send(&random_data(4096)).unwrap();
+        assert_eq!(remote.receive().unwrap().len(), 4096 + 1);
+        assert!(local_con.compression_ratio() < 1.0);
+    }
+}
+
+#[test]
+fn compress_then_encrypt() {
+    for compression in compressions() {
+        let (local, remote) = ChannelConnection::new_test_pair();
+        let (local, remote) = new_aes_encrypted_connection_test_pair(local, remote);
+        let mut local_con = CompressedConnection::new(local, compression);
+        let mut remote_con = CompressedConnection::new(remote, compression);
+
+        assert_eq!(local_con.compression_ratio(), 1.0);
+        for _ in 0..10 {
+            local_con.send(&compressible_data()).unwrap();
+            assert_eq!(remote_con.receive().unwrap(), compressible_data());
+        }
+        assert!(local_con.compression_ratio() > 4.0);
+    }
+}
+
+// returns the compressed representation of the packet
+fn compress(compression: Compression, packet: &[u8]) -> Vec<u8> {
+    let (local, mut remote) = ChannelConnection::new_test_pair();
+    CompressedConnection::new(local, compression).send(packet).unwrap();
+    remote.receive().unwrap()
+}
+
+#[test]
+fn invalid_packets() {
+    for compression in compressions() {
+        let (mut local, remote) = ChannelConnection::new_test_pair();
+        let mut remote_con = CompressedConnection::new(remote, compression).with_max_packet_size(4096);
+
+        local.send(&[]).unwrap();
+        assert!(matches!(remote_con.receive(), Err(Error::InvalidPacket)));
+
+        local.send(&[200, 0, 0, 0, 0]).unwrap();
+        assert!(matches!(remote_con.receive(), Err(Error::UnsupportedCompression { flag: 200 })));
+
+        let truncated = compress(compression, &[0; 2048]);
+        local.send(&truncated[..truncated.len() - 2]).unwrap();
+        assert!(matches!(remote_con.receive(), Err(Error::Decompress(_))));
+
+        local.send(&compress(compression, &[0; 8192])).unwrap();
+        assert!(matches!(remote_con.receive(), Err(Error::PacketTooLarge { size: 8192, limit: 4096 })));
+
+        local.send(&compress(compression, &[0; 2048])).unwrap();
+        assert_eq!(remote_con.receive().unwrap(), [0; 2048]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    net::UdpSocket,
+    thread,
+    time::{Duration, Instant},
+};
+
+use xs_rust_library::{
+    connection::{Connection, TimedReceive},
+    cryptography::{
+        encryption::aes256_crypto::Aes256Crypto,
+        key_exchange::{curve25519::Curve25519, HandshakeMode},
+    },
+    datagram_connection::{
+        fragment::{FragmentHeader, FRAGMENT_HEADER_SIZE},
+        fragment_assembly::FragmentAssembly,
+        DatagramConnection, Error,
+    },
+    encrypted_connection::EncryptedConnection,
+};
+
+fn new_datagram_connection_pair() -> (DatagramConnection, DatagramConnection) {
+    let local = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let remote = UdpSocket::bind("127.0.0.1:0").unwrap();
+    local.connect(remote.local_addr().unwrap()).unwrap();
+    remote.connect(local.local_addr().unwrap()).unwrap();
+    (DatagramConnection::new(local), DatagramConnection::new(remote))
+}
+
+fn fragment(packet_id: u32, index: u16, count: u16, data: &[u8]) -> Vec<u8> {
+    let mut datagram = FragmentHeader { packet_id, index, count }.to_bytes().to_vec();
+    datagram.extend_from_slice(data);
+    datagram
+}
+
+#[test]
+fn datagram_round_trip() {
+    let (mut local_con, mut remote_con) = new_datagram_connection_pair();
+
+    remote_con.send(b"test123").unwrap();
+    remote_con.send(b"").unwrap();
+    assert_eq!(local_con.receive().unwrap(), b"test123");
+    assert_eq!(local_con.receive().unwrap(), b"");
+}
+
+#[test]
+fn datagram_fragmentation() {
+    let (local_con, mut remote_con) = new_datagram_connection_pair();
+    let mut local_con = local_con.with_mtu(508);
+    let packet: Vec<u8> = (0..20_000).map(|i| i as u8).collect();
+
+    remote_con.send(&packet).unwrap();
+    local_con.send(&packet).unwrap();
+    assert_eq!(local_con.receive().unwrap(), packet);
+    assert_eq!(remote_con.receive().unwrap(), packet);
+}
+
+#[test]
+fn datagram_payload_too_large() {
+    let (local_con, _remote_con) = new_datagram_connection_pair();
+    let mut local_con = local_con.with_mtu(FRAGMENT_HEADER_SIZE + 1);
+    assert_eq!(local_con.max_packet_size(), u16::MAX as usize);
+    assert!(matches!(
+        local_con.send(&vec![0_u8; u16::MAX as usize + 1]),
+        Err(Error::PayloadTooLarge { .. })
+    ));
+}
+
+#[test]
+fn datagram_receive_timeout() {
+    let (mut local_con, mut remote_con) = new_datagram_connection_pair();
+
+    assert!(local_con.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+    assert!(local_con.try_receive().unwrap().is_none());
+
+    remote_con.send(&[3_u8; 5000]).unwrap();
+    assert_eq!(local_con.receive_timeout(Duration::from_secs(5)).unwrap().unwrap(), [3_u8; 5000]);
+}
+
+#[test]
+fn reassemble_out_of_order() {
+    let mut assembler = FragmentAssembly::new(Duration::from_secs(5));
+    let now = Instant::now();
+
+    assert!(assembler.push(&fragment(7, 2, 3, b"ghi"), now).is_none());
+    assert!(assembler.push(&fragment(7, 0, 3, b"abc"), now).is_none());
+    assert!(assembler.push(&fragment(7, 0, 3, b"abc"), now).is_none()); // duplicate
+    assert!(assembler.push(&fragment(7, 5, 3, b"xyz"), now).is_none()); // invalid index
+    assert!(assembler.push(&[1, 2, 3], now).is_none()); // too short
+    assert_eq!(assembler.push(&fragment(7, 1, 3, b"def"), now).unwrap(), b"abcdefghi");
+    assert_eq!(assembler.partial_packets(), 0);
+}
+
+#[test]
+fn reassembly_drops_lost_fragments() {
+    let mut assembler = FragmentAssembly::new(Duration::from_millis(100));
+    let now = Instant::now();
+
+    assert!(assembler.push(&fragment(1, 0, 2, b"abc"), now).is_none());
+    assert_eq!(assembler.partial_packets(), 1);
+
+    // the second fragment arrives after the timeout, so the packet can not be completed anymore
+    let later = now + Duration::from_millis(200);
+    assert!(assembler.push(&fragment(1, 1, 2, b"def"), later).is_none());
+    assert_eq!(assembler.partial_packets(), 1);
+    assembler.drop_expired(later + Duration::from_millis(200));
+    assert_eq!(assembler.partial_packets(), 0);
+}
+
+#[test]
+fn reassembly_rejects_oversized_packets() {
+    let mut assembler = FragmentAssembly::new(Duration::from_secs(5));
+    let now = Instant::now();
+
+    // the header announces a packet of about 64MiB, nothing is buffered for it
+    assert!(assembler.push(&fragment(1, 0, u16::MAX, &[0; 1000]), now).is_none());
+    assert_eq!(assembler.partial_packets(), 0);
+
+    assembler.set_max_packet_size(8);
+    assert!(assembler.push(&fragment(2, 0, 3, b"abc"), now).is_none());
+    assert_eq!(assembler.partial_packets(), 0);
+    assert!(assembler.push(&fragment(3, 0, 2, b"abcd"), now).is_none());
+    assert_eq!(assembler.push(&fragment(3, 1, 2, b"ef"), now).unwrap(), b"abcdef");
+}
+
+#[test]
+fn reassembly_drops_mismatched_fragments() {
+    let mut assembler = FragmentAssembly::new(Duration::from_secs(5));
+    let now = Instant::now();
+
+    assert!(assembler.push(&fragment(1, 0, 3, b"abc"), now).is_none());
+    assert_eq!(assembler.partial_packets(), 1);
+    assert!(assembler.push(&fragment(1, 1, 3, b"de"), now).is_none());
+    assert_eq!(assembler.partial_packets(), 0);
+
+    // the last fragment can't be bigger than the others
+    assert!(assembler.push(&fragment(2, 2, 3, b"ghij"), now).is_none());
+    assert!(assembler.push(&fragment(2, 0, 3, b"abc"), now).is_none());
+    assert_eq!(assembler.partial_packets(), 0);
+}
+
+#[test]
+fn encrypted_datagram_connection() {
+    let (local_con, remote_con) = new_datagram_connection_pair();
+
+    let join_handle = thread::spawn(move || {
+        let mut enc_con =
+            EncryptedConnection::<Aes256Crypto, _>::with_handshake(remote_con, Curve25519, HandshakeMode::Client).unwrap();
+        enc_con.send(b"top secret").unwrap();
+        enc_con.send(&[9_u8; 10_000]).unwrap();
+    });
+
+    let mut enc_con = EncryptedConnection::<Aes256Crypto, _>::with_handshake(local_con, Curve25519, HandshakeMode::Server).unwrap();
+    assert_eq!(enc_con.receive().unwrap(), b"top secret");
+    assert_eq!(enc_con.receive().unwrap(), [9_u8; 10_000]);
+
+    join_handle.join().unwrap();
+}
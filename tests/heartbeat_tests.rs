@@ -0,0 +1,141 @@
+mod util;
+
+use std::{
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use util::test_connections::ChannelConnection;
+use xs_rust_library::{
+    connection::{Connection, TimedReceive},
+    heartbeat_connection::{heartbeat::Heartbeat, Error, HeartbeatConnection},
+    packet_connection::{packet_receive_event::PacketReceiveEvent, PacketConnection},
+};
+
+const INTERVAL: Duration = Duration::from_millis(20);
+const TIMEOUT: Duration = Duration::from_millis(50);
+
+fn heartbeat_pair() -> (HeartbeatConnection<ChannelConnection>, HeartbeatConnection<ChannelConnection>) {
+    let (local, remote) = ChannelConnection::new_test_pair();
+    (
+        HeartbeatConnection::new(local).with_interval(INTERVAL).with_timeout(TIMEOUT),
+        HeartbeatConnection::new(remote).with_interval(INTERVAL).with_timeout(TIMEOUT),
+    )
+}
+
+#[test]
+fn heartbeat_state() {
+    let start = Instant::now();
+    let mut heartbeat = Heartbeat::new(INTERVAL, TIMEOUT);
+
+    heartbeat.poll(start).unwrap();
+    assert!(heartbeat.next_outgoing().is_none());
+    assert_eq!(heartbeat.next_timeout(), Some(start + INTERVAL));
+
+    heartbeat.poll(start + INTERVAL).unwrap();
+    let ping = heartbeat.next_outgoing().unwrap();
+    assert_eq!(heartbeat.next_timeout(), Some(start + INTERVAL + TIMEOUT));
+
+    let mut remote = Heartbeat::new(INTERVAL, TIMEOUT);
+    assert_eq!(remote.handle_frame(&ping, start).unwrap(), None);
+    let pong = remote.next_outgoing().unwrap();
+
+    heartbeat.handle_frame(&pong, start + INTERVAL * 2).unwrap();
+    assert_eq!(heartbeat.round_trip_time(), Some(INTERVAL));
+
+    heartbeat.poll(start + INTERVAL * 3).unwrap();
+    assert!(heartbeat.next_outgoing().is_some());
+    assert!(matches!(
+        heartbeat.poll(start + INTERVAL * 3 + TIMEOUT),
+        Err(Error::PeerTimeout { timeout: TIMEOUT })
+    ));
+}
+
+#[test]
+fn invalid_frames() {
+    let mut heartbeat = Heartbeat::default();
+    assert!(matches!(heartbeat.handle_frame(&[], Instant::now()), Err(Error::InvalidFrame)));
+    assert!(matches!(heartbeat.handle_frame(&[1, 2, 3], Instant::now()), Err(Error::InvalidFrame)));
+    assert!(matches!(heartbeat.handle_frame(&[9], Instant::now()), Err(Error::InvalidFrame)));
+}
+
+#[test]
+fn control_frames_are_filtered() {
+    let (mut local_con, mut remote_con) = heartbeat_pair();
+
+    let join_handle = thread::spawn(move || {
+        for i in 0..5_u8 {
+            thread::sleep(INTERVAL);
+            assert!(remote_con.try_receive().unwrap().is_none());
+            remote_con.send(&[i]).unwrap();
+        }
+        remote_con.receive_timeout(TIMEOUT).unwrap()
+    });
+
+    for i in 0..5_u8 {
+        assert_eq!(local_con.receive().unwrap(), [i]);
+    }
+    local_con.send(b"done").unwrap();
+    assert_eq!(join_handle.join().unwrap().unwrap(), b"done");
+}
+
+#[test]
+fn round_trip_time() {
+    let (mut local_con, mut remote_con) = heartbeat_pair();
+
+    let join_handle = thread::spawn(move || remote_con.receive());
+
+    assert!(local_con.round_trip_time().is_none());
+    assert!(local_con.receive_timeout(INTERVAL * 5).unwrap().is_none());
+    assert!(local_con.round_trip_time().unwrap() < TIMEOUT);
+
+    local_con.send(b"stop").unwrap();
+    assert_eq!(join_handle.join().unwrap().unwrap(), b"stop");
+}
+
+#[test]
+fn dead_peer() {
+    let (local, _silent_remote) = ChannelConnection::new_test_pair();
+    let mut local_con = HeartbeatConnection::new(local).with_interval(INTERVAL).with_timeout(TIMEOUT);
+
+    let start = Instant::now();
+    assert!(matches!(local_con.receive(), Err(Error::PeerTimeout { .. })));
+    assert!(start.elapsed() >= INTERVAL + TIMEOUT);
+}
+
+#[test]
+fn receive_event_stops_on_dead_peer() {
+    let listener = TcpListener::bind("127.0.0.1:7013").unwrap();
+    // the remote keeps the socket open but never answers, like a peer behind a broken link
+    let _silent_remote = TcpStream::connect("127.0.0.1:7013").unwrap();
+    let local_con = PacketConnection::new(listener.accept().unwrap().0, 1024);
+
+    let receive_event = PacketReceiveEvent::new(local_con).with_heartbeat(INTERVAL, TIMEOUT);
+    let start = Instant::now();
+    receive_event.start();
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn receive_event_with_heartbeat() {
+    let listener = TcpListener::bind("127.0.0.1:7014").unwrap();
+    let join_handle = thread::spawn(|| {
+        let remote = PacketConnection::new(TcpStream::connect("127.0.0.1:7014").unwrap(), 1024);
+        let mut remote_con = HeartbeatConnection::new(remote).with_interval(INTERVAL).with_timeout(TIMEOUT);
+        assert!(remote_con.receive_timeout(INTERVAL * 5).unwrap().is_none());
+        remote_con.send(b"test123").unwrap();
+    });
+
+    let local_con = PacketConnection::new(listener.accept().unwrap().0, 1024);
+    let mut receive_event = PacketReceiveEvent::new(local_con).with_heartbeat(INTERVAL, TIMEOUT);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let sender = std::sync::Mutex::new(sender);
+    let _subscription = receive_event.subscribe(Box::new(move |packet| sender.lock().unwrap().send(packet.clone()).unwrap()));
+
+    // returns once the remote closed the connection
+    receive_event.start();
+    join_handle.join().unwrap();
+    assert_eq!(receiver.try_recv().unwrap(), b"test123");
+    assert!(receive_event.round_trip_time().is_some());
+}
@@ -2,8 +2,12 @@ mod util;
 
 use std::thread;
 
-use util::test_connections::{ChannelConnection, FaultyConnection};
-use xs_rust_library::cryptography::key_exchange::{curve25519::Curve25519, HandshakeMode, KeyExchange};
+use util::test_connections::ChannelConnection;
+use xs_rust_library::{
+    chaos_connection::{chaos_config::ChaosConfig, ChaosConnection},
+    cryptography::key_exchange::{curve25519::Curve25519, HandshakeMode, KeyExchange},
+    memory_connection::MemoryConnection,
+};
 
 #[test]
 fn successful_key_exchange() {
@@ -19,5 +23,13 @@ fn successful_key_exchange() {
 
 #[test]
 fn bad_handshake() {
-    Curve25519.handshake(&mut FaultyConnection, HandshakeMode::Client).unwrap_err();
+    let (mut con_local, con_remote) = MemoryConnection::pair();
+    let mut con_remote = ChaosConnection::new(con_remote, ChaosConfig::new(0).with_truncate_probability(1.0));
+
+    let join_handle = thread::spawn(move || {
+        Curve25519.handshake(&mut con_remote, HandshakeMode::Client).unwrap();
+    });
+
+    Curve25519.handshake(&mut con_local, HandshakeMode::Server).unwrap_err();
+    join_handle.join().unwrap();
 }
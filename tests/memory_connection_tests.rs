@@ -0,0 +1,86 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use xs_rust_library::{
+    connection::{Connection, ConnectionReceiver, ConnectionSender, SplitConnection, TimedReceive},
+    encrypted_connection::EncryptedConnection,
+    encryption::aes256_crypto::Aes256Crypto,
+    key_exchange::{curve25519::Curve25519, HandshakeMode},
+    memory_connection::{Error, MemoryConnection},
+};
+
+#[test]
+fn send_and_receive() {
+    let (mut local, mut remote) = MemoryConnection::pair();
+
+    local.send(b"test123").unwrap();
+    local.send(&[5; 4096]).unwrap();
+    assert_eq!(remote.receive().unwrap(), b"test123");
+    assert_eq!(remote.try_receive().unwrap().unwrap(), [5; 4096]);
+    assert!(remote.try_receive().unwrap().is_none());
+    assert!(remote.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+
+    remote.send(b"abc").unwrap();
+    assert_eq!(local.receive_timeout(Duration::from_secs(1)).unwrap().unwrap(), b"abc");
+}
+
+#[test]
+fn disconnect() {
+    let (mut local, mut remote) = MemoryConnection::pair();
+
+    remote.send(b"last words").unwrap();
+    drop(remote);
+
+    assert_eq!(local.send(b"test"), Err(Error::Disconnected));
+    assert_eq!(local.receive().unwrap(), b"last words");
+    assert_eq!(local.receive(), Err(Error::Disconnected));
+    assert_eq!(local.receive_timeout(Duration::from_millis(10)), Err(Error::Disconnected));
+    assert_eq!(local.try_receive(), Err(Error::Disconnected));
+}
+
+#[test]
+fn bounded_backpressure() {
+    let (mut local, mut remote) = MemoryConnection::bounded_pair(2);
+    let sent_all = Arc::new(AtomicBool::new(false));
+
+    let join_handle = {
+        let sent_all = sent_all.clone();
+        thread::spawn(move || {
+            for i in 0..3_u8 {
+                local.send(&[i]).unwrap();
+            }
+            sent_all.store(true, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!sent_all.load(Ordering::SeqCst));
+    assert_eq!(remote.receive().unwrap(), [0]);
+    join_handle.join().unwrap();
+    assert!(sent_all.load(Ordering::SeqCst));
+    assert_eq!(remote.receive().unwrap(), [1]);
+    assert_eq!(remote.receive().unwrap(), [2]);
+}
+
+#[test]
+fn encrypted_handshake() {
+    let (local, remote) = MemoryConnection::pair();
+
+    let join_handle = thread::spawn(move || {
+        let mut remote = EncryptedConnection::<Aes256Crypto, _>::with_handshake(remote, Curve25519, HandshakeMode::Client).unwrap();
+        let packet = remote.receive().unwrap();
+        remote.send(&packet).unwrap();
+    });
+
+    let local = EncryptedConnection::<Aes256Crypto, _>::with_handshake(local, Curve25519, HandshakeMode::Server).unwrap();
+    let (mut sender, mut receiver) = local.split().unwrap();
+    sender.send(b"top secret").unwrap();
+    assert_eq!(receiver.receive().unwrap(), b"top secret");
+    join_handle.join().unwrap();
+}
@@ -0,0 +1,242 @@
+mod util;
+
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use xs_rust_library::{
+    connection::{Connection, TimedReceive},
+    cryptography::{
+        encryption::aes256_crypto::Aes256Crypto,
+        key_exchange::{curve25519::Curve25519, HandshakeMode},
+    },
+    encrypted_connection::EncryptedConnection,
+    multiplexer::{frame::Frame, Error, Multiplexer},
+    packet_connection::PacketConnection,
+};
+
+use crate::util::test_connections::ChannelConnection;
+
+fn new_multiplexer_pair() -> (Multiplexer<ChannelConnection>, Multiplexer<ChannelConnection>) {
+    let (local_con, remote_con) = ChannelConnection::new_test_pair();
+    (Multiplexer::new(local_con), Multiplexer::new(remote_con))
+}
+
+#[test]
+fn frame_round_trip() {
+    let frames = [
+        Frame::Open { stream: 1 },
+        Frame::Data {
+            stream: 2,
+            payload: b"abc",
+            end_of_message: false,
+        },
+        Frame::Data {
+            stream: 2,
+            payload: b"",
+            end_of_message: true,
+        },
+        Frame::Close { stream: 3 },
+        Frame::WindowUpdate { stream: 4, credit: 1024 },
+    ];
+
+    for frame in frames {
+        let mut encoded = Vec::new();
+        frame.encode(&mut encoded);
+        assert_eq!(Frame::parse(&encoded), Some(frame));
+    }
+    assert_eq!(Frame::parse(&[0, 1, 2]), None);
+}
+
+#[test]
+fn open_and_accept() {
+    let (local, remote) = new_multiplexer_pair();
+
+    let mut local_stream = local.open().unwrap();
+    let mut other_local_stream = local.open().unwrap();
+    let mut remote_stream = remote.accept().unwrap();
+    let mut other_remote_stream = remote.accept().unwrap();
+    assert!(local_stream.is_local());
+    assert!(!remote_stream.is_local());
+    assert_ne!(local_stream.id(), other_local_stream.id());
+
+    other_local_stream.send(b"second").unwrap();
+    local_stream.send(b"first").unwrap();
+    local_stream.send(b"").unwrap();
+    assert_eq!(remote_stream.receive().unwrap(), b"first");
+    assert_eq!(remote_stream.receive().unwrap(), b"");
+    assert_eq!(other_remote_stream.receive().unwrap(), b"second");
+
+    // streams opened by the remote do not collide with local ones
+    let mut reverse_stream = remote.open().unwrap();
+    reverse_stream.send(b"reverse").unwrap();
+    let mut accepted = local.accept().unwrap();
+    assert_ne!(accepted.id(), local_stream.id());
+    assert_eq!(accepted.receive().unwrap(), b"reverse");
+}
+
+#[test]
+fn multiplexer_timeouts() {
+    let (local, remote) = new_multiplexer_pair();
+
+    assert!(remote.try_accept().unwrap().is_none());
+    assert!(remote.accept_timeout(Duration::from_millis(10)).unwrap().is_none());
+
+    let mut local_stream = local.open().unwrap();
+    let mut remote_stream = remote.accept_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    assert!(remote_stream.try_receive().unwrap().is_none());
+    assert!(remote_stream.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+
+    local_stream.send(b"test123").unwrap();
+    assert_eq!(remote_stream.receive_timeout(Duration::from_secs(5)).unwrap().unwrap(), b"test123");
+}
+
+#[test]
+fn large_transfer_does_not_starve_other_streams() {
+    const MESSAGE_COUNT: usize = 64;
+    const MESSAGE_SIZE: usize = 64 * 1024;
+    let (local, remote) = new_multiplexer_pair();
+
+    let mut bulk_stream = local.open().unwrap();
+    let mut chat_stream = local.open().unwrap();
+    let mut remote_bulk_stream = remote.accept().unwrap();
+    let mut remote_chat_stream = remote.accept().unwrap();
+
+    let bulk_thread = thread::spawn(move || {
+        for i in 0..MESSAGE_COUNT {
+            bulk_stream.send(&[i as u8; MESSAGE_SIZE]).unwrap();
+        }
+        bulk_stream
+    });
+
+    chat_stream.send(b"still there?").unwrap();
+    assert_eq!(remote_chat_stream.receive().unwrap(), b"still there?");
+
+    // the bulk sender runs out of credit since nothing of the bulk stream was read yet
+    thread::sleep(Duration::from_millis(100));
+    assert!(!bulk_thread.is_finished());
+
+    for i in 0..MESSAGE_COUNT {
+        assert_eq!(remote_bulk_stream.receive().unwrap(), [i as u8; MESSAGE_SIZE]);
+    }
+    let _bulk_stream = bulk_thread.join().unwrap();
+}
+
+#[test]
+fn message_bigger_than_window() {
+    let (local, remote) = new_multiplexer_pair();
+    let mut local_stream = local.open().unwrap();
+    let mut remote_stream = remote.accept().unwrap();
+    let message: Vec<u8> = (0..1024 * 1024).map(|i| i as u8).collect();
+
+    let expected = message.clone();
+    let receive_thread = thread::spawn(move || {
+        assert_eq!(remote_stream.receive().unwrap(), expected);
+    });
+
+    local_stream.send(&message).unwrap();
+    receive_thread.join().unwrap();
+}
+
+#[test]
+fn unread_messages_hold_back_credit() {
+    let (local, remote) = new_multiplexer_pair();
+    let mut local_stream = local.open().unwrap();
+    let mut remote_stream = remote.accept().unwrap();
+    let mut remote_idle_stream = remote.open().unwrap();
+    let _local_idle_stream = local.accept().unwrap();
+
+    local_stream.send(b"first").unwrap();
+    let sent = Arc::new(AtomicBool::new(false));
+    let sent_copy = sent.clone();
+    let send_thread = thread::spawn(move || {
+        local_stream.send(&[7; 1024 * 1024]).unwrap();
+        sent_copy.store(true, Ordering::SeqCst);
+        local_stream
+    });
+
+    // frames are dispatched while waiting on another stream, but the unread message keeps the window closed
+    assert!(remote_idle_stream.receive_timeout(Duration::from_millis(300)).unwrap().is_none());
+    assert!(!sent.load(Ordering::SeqCst));
+
+    assert_eq!(remote_stream.receive().unwrap(), b"first");
+    assert_eq!(remote_stream.receive().unwrap(), [7; 1024 * 1024]);
+    let _local_stream = send_thread.join().unwrap();
+}
+
+#[test]
+fn max_message_size() {
+    let (local, remote) = new_multiplexer_pair();
+    let remote = remote.with_max_message_size(64 * 1024);
+    let mut local_stream = local.open().unwrap();
+    let mut remote_stream = remote.accept().unwrap();
+
+    assert!(matches!(
+        remote_stream.send(&[1; 64 * 1024 + 1]),
+        Err(Error::MessageTooLarge { limit: 65536 })
+    ));
+
+    // the oversized message is discarded and the stream is closed for both sides
+    local_stream.send(&[1; 128 * 1024]).unwrap();
+    assert!(matches!(remote_stream.receive(), Err(Error::MessageTooLarge { limit: 65536 })));
+    assert!(matches!(local_stream.receive(), Err(Error::StreamClosed)));
+}
+
+#[test]
+fn close_stream() {
+    let (local, remote) = new_multiplexer_pair();
+    let mut local_stream = local.open().unwrap();
+    let mut remote_stream = remote.accept().unwrap();
+
+    local_stream.send(b"last words").unwrap();
+    local_stream.close().unwrap();
+
+    // buffered messages are still delivered before the stream reports being closed
+    assert_eq!(remote_stream.receive().unwrap(), b"last words");
+    assert!(matches!(remote_stream.receive(), Err(Error::StreamClosed)));
+    assert!(matches!(remote_stream.send(b"too late"), Err(Error::StreamClosed)));
+
+    // dropping a stream closes it as well
+    let dropped_stream = remote.open().unwrap();
+    let mut local_stream = local.accept().unwrap();
+    drop(dropped_stream);
+    remote.open().unwrap().send(b"flushes the close").unwrap();
+    assert!(matches!(local_stream.receive(), Err(Error::StreamClosed)));
+}
+
+#[test]
+fn encrypted_multiplexer() {
+    let listener = TcpListener::bind("127.0.0.1:7011").unwrap();
+
+    let join_handle = thread::spawn(move || {
+        let remote_con = PacketConnection::new(listener.accept().unwrap().0, 1024);
+        let enc_con = EncryptedConnection::<Aes256Crypto, _>::with_handshake(remote_con, Curve25519, HandshakeMode::Server).unwrap();
+        let remote = Multiplexer::new(enc_con);
+
+        let mut streams: Vec<_> = (0..10).map(|_| remote.accept().unwrap()).collect();
+        for stream in streams.iter_mut() {
+            let packet = stream.receive().unwrap();
+            stream.send(&packet).unwrap();
+        }
+    });
+
+    let local_con = PacketConnection::new(TcpStream::connect("127.0.0.1:7011").unwrap(), 1024);
+    let enc_con = EncryptedConnection::<Aes256Crypto, _>::with_handshake(local_con, Curve25519, HandshakeMode::Client).unwrap();
+    let local = Multiplexer::new(enc_con);
+
+    let mut streams: Vec<_> = (0..10).map(|_| local.open().unwrap()).collect();
+    for (i, stream) in streams.iter_mut().enumerate() {
+        stream.send(format!("stream {i}").as_bytes()).unwrap();
+    }
+    for (i, stream) in streams.iter_mut().enumerate().rev() {
+        assert_eq!(stream.receive().unwrap(), format!("stream {i}").as_bytes());
+    }
+
+    join_handle.join().unwrap();
+}
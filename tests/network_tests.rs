@@ -3,16 +3,25 @@ mod util;
 
 #[cfg(test)]
 mod network_tests {
-    use std::{net::*, sync::*, thread};
+    use std::{
+        io::{Cursor, Write},
+        net::*,
+        sync::*,
+        thread,
+        time::Duration,
+    };
 
     use xs_rust_library::{
-        connection::Connection,
+        connection::{Connection, ConnectionReceiver, ConnectionSender, SplitConnection, TimedReceive},
         cryptography::{
             encryption::aes256_crypto::Aes256Crypto,
             key_exchange::{curve25519::Curve25519, HandshakeMode},
         },
-        encrypted_connection::EncryptedConnection,
-        packet_connection::{packet_receive_event::PacketReceiveEvent, PacketConnection},
+        encrypted_connection::{self, EncryptedConnection},
+        packet_connection::{
+            self, byte_stream::DuplexStream, packet_assembly, packet_codec::{FrameChecksum, FrameFormat}, packet_receive_event::PacketReceiveEvent,
+            PacketConnection,
+        },
     };
 
     use crate::{
@@ -78,6 +87,63 @@ mod network_tests {
         packet_connection.send(&[0 as u8; 8]).expect("sending failed");
     }
 
+    #[test]
+    fn split_connection() {
+        const PACKET_COUNT: u32 = 100;
+        let listener = TcpListener::bind("127.0.0.1:7004").unwrap();
+
+        // echo server
+        let join_handle = thread::spawn(move || {
+            let mut remote_con = PacketConnection::new(listener.accept().unwrap().0, 1024);
+            for _ in 0..PACKET_COUNT {
+                let packet = remote_con.receive().unwrap();
+                remote_con.send(&packet).unwrap();
+            }
+        });
+
+        let local_con = PacketConnection::new(TcpStream::connect("127.0.0.1:7004").unwrap(), 1024);
+        let (mut sender, mut receiver) = local_con.split().unwrap();
+
+        let send_thread = thread::spawn(move || {
+            for i in 0..PACKET_COUNT {
+                sender.send(&i.to_le_bytes()).unwrap();
+            }
+        });
+
+        let receive_thread = thread::spawn(move || {
+            for i in 0..PACKET_COUNT {
+                assert_eq!(receiver.receive().unwrap(), i.to_le_bytes());
+            }
+        });
+
+        send_thread.join().unwrap();
+        receive_thread.join().unwrap();
+        join_handle.join().unwrap();
+    }
+
+    #[test]
+    fn split_encrypted_connection() {
+        let listener = TcpListener::bind("127.0.0.1:7005").unwrap();
+
+        let join_handle = thread::spawn(move || {
+            let remote_con = PacketConnection::new(listener.accept().unwrap().0, 1024);
+            let mut enc_con =
+                EncryptedConnection::<Aes256Crypto, _>::with_handshake(remote_con, Curve25519, HandshakeMode::Server).unwrap();
+            let packet = enc_con.receive().unwrap();
+            enc_con.send(&packet).unwrap();
+        });
+
+        let local_con = PacketConnection::new(TcpStream::connect("127.0.0.1:7005").unwrap(), 1024);
+        let enc_con = EncryptedConnection::<Aes256Crypto, _>::with_handshake(local_con, Curve25519, HandshakeMode::Client).unwrap();
+        let (mut sender, mut receiver) = enc_con.split().unwrap();
+
+        let send_thread = thread::spawn(move || sender.send(b"top secret").unwrap());
+        assert_eq!(receiver.receive().unwrap(), b"top secret");
+
+        send_thread.join().unwrap();
+        join_handle.join().unwrap();
+    }
+
     #[test]
     fn receive_event_test() {
         let listening_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
@@ -177,6 +243,319 @@ mod network_tests {
         join_handle.join().unwrap();
     }
 
+    #[test]
+    fn max_packet_size() {
+        let listener = TcpListener::bind("127.0.0.1:7001").unwrap();
+
+        let join_handle = thread::spawn(move || {
+            let mut remote_stream = TcpStream::connect("127.0.0.1:7001").unwrap();
+            // announce a 4 GiB packet without ever sending the data
+            remote_stream.write_all(&u32::MAX.to_le_bytes()).unwrap();
+            remote_stream
+        });
+
+        let (local_stream, _) = listener.accept().unwrap();
+        let mut local_con = PacketConnection::new(local_stream, 1024).with_max_packet_size(1024);
+        match local_con.receive() {
+            Err(packet_connection::Error::PacketAssembly(packet_assembly::Error::PacketTooLarge { announced, limit })) => {
+                assert_eq!(announced, u32::MAX as usize);
+                assert_eq!(limit, 1024);
+            }
+            _ => panic!("oversized packet was not rejected"),
+        }
+
+        join_handle.join().unwrap();
+    }
+
+    #[test]
+    fn max_packet_size_boundary() {
+        let listener = TcpListener::bind("127.0.0.1:7002").unwrap();
+
+        let join_handle = thread::spawn(move || {
+            let remote_stream = TcpStream::connect("127.0.0.1:7002").unwrap();
+            let mut remote_con = PacketConnection::new(remote_stream, 1024);
+            remote_con.send(&[1_u8; 4096]).unwrap();
+            remote_con.send(&[1_u8; 4097]).unwrap();
+        });
+
+        let (local_stream, _) = listener.accept().unwrap();
+        let mut local_con = PacketConnection::new(local_stream, 1024).with_max_packet_size(4096);
+        assert_eq!(local_con.receive().unwrap().len(), 4096);
+        assert!(local_con.receive().is_err());
+
+        join_handle.join().unwrap();
+    }
+
+    #[test]
+    fn receive_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:7003").unwrap();
+        let mut remote_stream = TcpStream::connect("127.0.0.1:7003").unwrap();
+        let mut local_con = PacketConnection::new(listener.accept().unwrap().0, 1024);
+
+        assert!(local_con.receive_timeout(Duration::from_millis(50)).unwrap().is_none());
+        assert!(local_con.try_receive().unwrap().is_none());
+
+        // only send part of the packet, the partial data has to survive the timeouts
+        remote_stream.write_all(&[5, 0, 0, 0, 1, 2]).unwrap();
+        assert!(local_con.receive_timeout(Duration::from_millis(50)).unwrap().is_none());
+        assert!(local_con.try_receive().unwrap().is_none());
+
+        remote_stream.write_all(&[3, 4, 5]).unwrap();
+        assert_eq!(local_con.receive_timeout(Duration::from_secs(5)).unwrap().unwrap(), [1, 2, 3, 4, 5]);
+
+        // connection is still fully usable in blocking mode
+        remote_stream.write_all(&[1, 0, 0, 0, 9]).unwrap();
+        assert_eq!(local_con.receive().unwrap(), [9]);
+    }
+
+    #[test]
+    fn encrypted_receive_timeout() {
+        let (local_con, remote_con) = ChannelConnection::new_test_pair();
+        let (mut local_con, mut remote_con) = new_aes_encrypted_connection_test_pair(local_con, remote_con);
+
+        assert!(local_con.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+        assert!(local_con.try_receive().unwrap().is_none());
+
+        remote_con.send(b"top secret").unwrap();
+        assert_eq!(local_con.receive_timeout(Duration::from_secs(5)).unwrap().unwrap(), b"top secret");
+    }
+
+    #[test]
+    fn cursor_connection() {
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024);
+        writing_con.send(b"test123").unwrap();
+        writing_con.send(&[5_u8; 4096]).unwrap();
+
+        let written = writing_con.stream_mut().get_mut().clone();
+        let mut reading_con = PacketConnection::new(Cursor::new(written), 1024);
+        assert_eq!(reading_con.receive().unwrap(), b"test123");
+        assert_eq!(reading_con.receive().unwrap(), [5_u8; 4096]);
+        assert!(reading_con.receive().is_err());
+    }
+
+    #[test]
+    fn receive_into_connection() {
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024);
+        writing_con.send(&[1_u8; 4096]).unwrap();
+        writing_con.send(b"test123").unwrap();
+        writing_con.send(&[]).unwrap();
+        writing_con.send(&[2_u8; 100]).unwrap();
+
+        let written = writing_con.stream_mut().get_mut().clone();
+        let mut reading_con = PacketConnection::new(Cursor::new(written), 16);
+        let mut buffer = b"stale".to_vec();
+        reading_con.receive_into(&mut buffer).unwrap();
+        assert_eq!(buffer, [1_u8; 4096]);
+        reading_con.receive_into(&mut buffer).unwrap();
+        assert_eq!(buffer, b"test123");
+        reading_con.receive_into(&mut buffer).unwrap();
+        assert!(buffer.is_empty());
+        reading_con.receive_into(&mut buffer).unwrap();
+        assert_eq!(buffer, [2_u8; 100]);
+        assert!(reading_con.receive_into(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn stream_connection() {
+        const PAYLOAD_SIZE: usize = 200 * 1024; // spans several stream chunks
+        let payload: Vec<u8> = (0..PAYLOAD_SIZE).map(|i| i as u8).collect();
+
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024).with_checksum(FrameChecksum::Crc32c);
+        writing_con.send(b"before").unwrap();
+        writing_con.send_stream(payload.as_slice(), PAYLOAD_SIZE).unwrap();
+        writing_con.send_stream([].as_slice(), 0).unwrap();
+        writing_con.send(b"after").unwrap();
+
+        let written = writing_con.stream_mut().get_mut().clone();
+        let mut reading_con = PacketConnection::new(Cursor::new(written), 100).with_checksum(FrameChecksum::Crc32c);
+        let mut received = Vec::new();
+        assert_eq!(reading_con.receive_stream(&mut received).unwrap(), 6);
+        assert_eq!(received, b"before");
+
+        received.clear();
+        assert_eq!(reading_con.receive_stream(&mut received).unwrap(), PAYLOAD_SIZE);
+        assert_eq!(received, payload);
+        assert_eq!(reading_con.receive_stream(std::io::sink()).unwrap(), 0);
+        assert_eq!(reading_con.receive().unwrap(), b"after");
+    }
+
+    #[test]
+    fn stream_reader_too_short() {
+        let mut con = PacketConnection::new(Cursor::new(Vec::new()), 1024);
+        assert!(matches!(
+            con.send_stream([1_u8; 10].as_slice(), 11),
+            Err(packet_connection::Error::StreamRead(_))
+        ));
+    }
+
+    #[test]
+    fn encrypted_stream() {
+        const PAYLOAD_SIZE: usize = 150 * 1024;
+        let payload: Vec<u8> = (0..PAYLOAD_SIZE).map(|i| (i % 251) as u8).collect();
+
+        let (local_con, remote_con) = ChannelConnection::new_test_pair();
+        let (mut local_con, mut remote_con) = new_aes_encrypted_connection_test_pair(local_con, remote_con);
+
+        remote_con.send_stream(payload.as_slice(), PAYLOAD_SIZE).unwrap();
+        remote_con.send(b"after").unwrap();
+
+        let mut received = Vec::new();
+        assert_eq!(local_con.receive_stream(&mut received).unwrap(), PAYLOAD_SIZE);
+        assert_eq!(received, payload);
+        assert_eq!(local_con.receive().unwrap(), b"after");
+    }
+
+    #[test]
+    fn encrypted_stream_out_of_order() {
+        let (local_con, remote_con) = ChannelConnection::new_test_pair();
+        let (mut local_con, mut remote_con) = new_aes_encrypted_connection_test_pair(local_con, remote_con);
+
+        // announce a stream but send a chunk with the wrong index
+        let mut start = 7_u64.to_le_bytes().to_vec();
+        start.extend_from_slice(&10_u64.to_le_bytes());
+        remote_con.send(&start).unwrap();
+        let mut chunk = start.clone();
+        chunk.extend_from_slice(&1_u64.to_le_bytes());
+        chunk.extend_from_slice(&[0_u8; 10]);
+        remote_con.send(&chunk).unwrap();
+
+        assert!(matches!(
+            local_con.receive_stream(std::io::sink()),
+            Err(encrypted_connection::TransmissionError::InvalidStreamChunk)
+        ));
+    }
+
+    #[test]
+    fn encrypted_stream_spliced_chunk() {
+        let (local_con, remote_con) = ChannelConnection::new_test_pair();
+        let (mut local_con, mut remote_con) = new_aes_encrypted_connection_test_pair(local_con, remote_con);
+
+        // capture the encrypted frames of two streams with the same length
+        remote_con.send_stream([1_u8; 10].as_slice(), 10).unwrap();
+        remote_con.send_stream([2_u8; 10].as_slice(), 10).unwrap();
+        let frames: Vec<_> = (0..4).map(|_| local_con.get_underlying_connection().receive().unwrap()).collect();
+
+        // start of the second stream followed by the chunk of the first one
+        remote_con.get_underlying_connection().send(&frames[2]).unwrap();
+        remote_con.get_underlying_connection().send(&frames[1]).unwrap();
+        assert!(matches!(
+            local_con.receive_stream(std::io::sink()),
+            Err(encrypted_connection::TransmissionError::InvalidStreamChunk)
+        ));
+    }
+
+    #[test]
+    fn encrypted_stream_aborted() {
+        let (local_con, remote_con) = ChannelConnection::new_test_pair();
+        let (mut local_con, mut remote_con) = new_aes_encrypted_connection_test_pair(local_con, remote_con);
+
+        // the reader ends after the first chunk
+        let payload = vec![3_u8; 100 * 1024];
+        assert!(matches!(
+            remote_con.send_stream(payload.as_slice(), 200 * 1024),
+            Err(encrypted_connection::TransmissionError::StreamRead(_))
+        ));
+        remote_con.send(b"after").unwrap();
+
+        assert!(matches!(
+            local_con.receive_stream(std::io::sink()),
+            Err(encrypted_connection::TransmissionError::StreamAborted)
+        ));
+        assert_eq!(local_con.receive().unwrap(), b"after");
+    }
+
+    #[test]
+    fn encrypted_receive_into() {
+        let (local_con, remote_con) = ChannelConnection::new_test_pair();
+        let (mut local_con, mut remote_con) = new_aes_encrypted_connection_test_pair(local_con, remote_con);
+
+        let mut buffer = Vec::new();
+        for packet in [b"top secret".as_slice(), &[3_u8; 2048], b"short"] {
+            remote_con.send(packet).unwrap();
+            local_con.receive_into(&mut buffer).unwrap();
+            assert_eq!(buffer, packet);
+        }
+    }
+
+    #[test]
+    fn tiny_receive_buffer() {
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024);
+        writing_con.send(b"abc").unwrap();
+        writing_con.send(b"defgh").unwrap();
+
+        // a buffer smaller than the header forces the header to be split across reads
+        let written = writing_con.stream_mut().get_mut().clone();
+        let mut reading_con = PacketConnection::new(Cursor::new(written), 3);
+        assert_eq!(reading_con.receive().unwrap(), b"abc");
+        assert_eq!(reading_con.receive().unwrap(), b"defgh");
+    }
+
+    #[test]
+    fn frame_format_connection() {
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024).with_frame_format(FrameFormat::U16BigEndian);
+        writing_con.send(b"abc").unwrap();
+        assert!(matches!(
+            writing_con.send(&[0_u8; 70_000]),
+            Err(packet_connection::Error::PayloadTooLarge { size: 70_000, .. })
+        ));
+
+        let written = writing_con.stream_mut().get_mut().clone();
+        assert_eq!(written, [0, 3, b'a', b'b', b'c']);
+
+        let mut reading_con = PacketConnection::new(Cursor::new(written), 1024).with_frame_format(FrameFormat::U16BigEndian);
+        assert_eq!(reading_con.receive().unwrap(), b"abc");
+    }
+
+    #[test]
+    fn checksum_connection() {
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024).with_checksum(FrameChecksum::Crc32c);
+        writing_con.send(b"abc").unwrap();
+        writing_con.send(b"defgh").unwrap();
+
+        let mut written = writing_con.stream_mut().get_mut().clone();
+        written[16] ^= 0x80; // corrupt the payload of the second packet
+
+        let mut reading_con = PacketConnection::new(Cursor::new(written), 1024).with_checksum(FrameChecksum::Crc32c);
+        assert_eq!(reading_con.receive().unwrap(), b"abc");
+        assert!(matches!(
+            reading_con.receive(),
+            Err(packet_connection::Error::PacketAssembly(packet_assembly::Error::ChecksumMismatch))
+        ));
+    }
+
+    #[test]
+    fn duplex_stream_connection() {
+        let mut writing_con = PacketConnection::new(Cursor::new(Vec::new()), 1024);
+        writing_con.send(b"request").unwrap();
+        let incoming = writing_con.stream_mut().get_mut().clone();
+
+        let mut duplex_con = PacketConnection::new(DuplexStream::new(Cursor::new(incoming), Vec::new()), 1024);
+        assert_eq!(duplex_con.receive().unwrap(), b"request");
+        duplex_con.send(b"response").unwrap();
+
+        let (_, outgoing) = duplex_con.stream().get_ref();
+        let mut reading_con = PacketConnection::new(Cursor::new(outgoing.clone()), 1024);
+        assert_eq!(reading_con.receive().unwrap(), b"response");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_stream_connection() {
+        let (local_stream, remote_stream) = std::os::unix::net::UnixStream::pair().unwrap();
+
+        let join_handle = thread::spawn(move || {
+            let mut remote_con = PacketConnection::new(remote_stream, 1024);
+            let packet = remote_con.receive().unwrap();
+            remote_con.send(&packet).unwrap();
+        });
+
+        let mut local_con = PacketConnection::new(local_stream, 1024);
+        local_con.send(b"echo").unwrap();
+        assert_eq!(local_con.receive().unwrap(), b"echo");
+
+        join_handle.join().unwrap();
+    }
+
     #[test]
     #[ignore]
     fn performance_tests() {
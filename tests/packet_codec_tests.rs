@@ -0,0 +1,245 @@
+use xs_rust_library::packet_connection::packet_codec::{
+    DecodeError, EncodeError, FrameChecksum, FrameFormat, PacketDecoder, PacketEncoder, StreamChunk, CHECKSUM_SIZE,
+};
+
+const FRAME_FORMATS: &[FrameFormat] = &[
+    FrameFormat::U32LittleEndian,
+    FrameFormat::U32BigEndian,
+    FrameFormat::U16LittleEndian,
+    FrameFormat::U16BigEndian,
+    FrameFormat::Leb128,
+];
+
+fn encode_all(packets: &[&[u8]]) -> Vec<u8> {
+    let encoder = PacketEncoder::new();
+    let mut encoded = Vec::new();
+    for packet in packets {
+        encoder.encode(packet, &mut encoded).unwrap();
+    }
+    encoded
+}
+
+#[test]
+fn encode_packet() {
+    assert_eq!(encode_all(&[&[7, 8, 9]]), vec![3, 0, 0, 0, 7, 8, 9]);
+}
+
+#[test]
+fn decode_in_single_push() {
+    let encoded = encode_all(&[b"test123", b"", &[5_u8; 2048]]);
+
+    let mut decoder = PacketDecoder::new(4096);
+    decoder.push(&encoded).unwrap();
+    assert_eq!(decoder.next_packet().unwrap(), b"test123");
+    assert_eq!(decoder.next_packet().unwrap(), b"");
+    assert_eq!(decoder.next_packet().unwrap(), [5_u8; 2048]);
+    assert!(decoder.next_packet().is_none());
+    assert!(!decoder.has_partial_packet());
+}
+
+#[test]
+fn decode_byte_by_byte() {
+    let encoded = encode_all(&[b"abc", b"defgh"]);
+
+    let mut decoder = PacketDecoder::new(4096);
+    let mut packets = Vec::new();
+    for byte in encoded {
+        decoder.push(&[byte]).unwrap();
+        while let Some(packet) = decoder.next_packet() {
+            packets.push(packet);
+        }
+    }
+
+    assert_eq!(packets, vec![b"abc".to_vec(), b"defgh".to_vec()]);
+}
+
+#[test]
+fn decode_partial_packet() {
+    let encoded = encode_all(&[b"abcdef"]);
+
+    let mut decoder = PacketDecoder::new(4096);
+    decoder.push(&encoded[..2]).unwrap();
+    assert!(decoder.has_partial_packet());
+    decoder.push(&encoded[2..7]).unwrap();
+    assert!(decoder.next_packet().is_none());
+    decoder.push(&encoded[7..]).unwrap();
+    assert_eq!(decoder.next_packet().unwrap(), b"abcdef");
+}
+
+#[test]
+fn decode_empty_packet_at_end_of_chunk() {
+    let encoded = encode_all(&[b"abc", b""]);
+
+    let mut decoder = PacketDecoder::new(4096);
+    decoder.push(&encoded[..5]).unwrap();
+    decoder.push(&encoded[5..]).unwrap();
+    assert_eq!(decoder.next_packet().unwrap(), b"abc");
+    assert_eq!(decoder.next_packet().unwrap(), b"");
+    assert!(!decoder.has_partial_packet());
+}
+
+#[test]
+fn decode_too_large_packet() {
+    let mut decoder = PacketDecoder::new(16);
+    match decoder.push(&u32::MAX.to_le_bytes()) {
+        Err(DecodeError::PacketTooLarge { announced, limit }) => {
+            assert_eq!(announced, u32::MAX as usize);
+            assert_eq!(limit, 16);
+        }
+        _ => panic!("oversized packet was not rejected"),
+    }
+}
+
+#[test]
+fn frame_format_headers() {
+    let header = |frame_format, size| PacketEncoder::with_frame_format(frame_format).header(size).unwrap().to_vec();
+
+    assert_eq!(header(FrameFormat::U32LittleEndian, 300), vec![0x2c, 0x01, 0, 0]);
+    assert_eq!(header(FrameFormat::U32BigEndian, 300), vec![0, 0, 0x01, 0x2c]);
+    assert_eq!(header(FrameFormat::U16LittleEndian, 300), vec![0x2c, 0x01]);
+    assert_eq!(header(FrameFormat::U16BigEndian, 300), vec![0x01, 0x2c]);
+    assert_eq!(header(FrameFormat::Leb128, 0), vec![0]);
+    assert_eq!(header(FrameFormat::Leb128, 127), vec![0x7f]);
+    assert_eq!(header(FrameFormat::Leb128, 300), vec![0xac, 0x02]);
+}
+
+#[test]
+fn frame_format_round_trip() {
+    let packets: &[&[u8]] = &[b"", b"abc", &[7_u8; 300], &[9_u8; 70_000]];
+
+    for &frame_format in FRAME_FORMATS {
+        let encoder = PacketEncoder::with_frame_format(frame_format);
+        let mut decoder = PacketDecoder::with_frame_format(frame_format, usize::MAX);
+
+        let mut encoded = Vec::new();
+        for packet in packets {
+            // the u16 formats are not able to express the biggest packet
+            if packet.len() <= frame_format.max_payload_size() {
+                encoder.encode(packet, &mut encoded).unwrap();
+            }
+        }
+
+        for chunk in encoded.chunks(7) {
+            decoder.push(chunk).unwrap();
+        }
+
+        for packet in packets.iter().filter(|p| p.len() <= frame_format.max_payload_size()) {
+            assert_eq!(&decoder.next_packet().unwrap(), packet, "{frame_format:?}");
+        }
+        assert!(decoder.next_packet().is_none());
+    }
+}
+
+#[test]
+fn payload_exceeds_header() {
+    let encoder = PacketEncoder::with_frame_format(FrameFormat::U16LittleEndian);
+    let mut encoded = Vec::new();
+    match encoder.encode(&[0_u8; 70_000], &mut encoded) {
+        Err(EncodeError::PayloadTooLarge { size, limit }) => {
+            assert_eq!(size, 70_000);
+            assert_eq!(limit, u16::MAX as usize);
+        }
+        _ => panic!("payload was silently truncated"),
+    }
+    assert!(encoded.is_empty());
+}
+
+#[test]
+fn invalid_leb128_header() {
+    let mut decoder = PacketDecoder::with_frame_format(FrameFormat::Leb128, usize::MAX);
+    assert!(matches!(decoder.push(&[0xff; 11]), Err(DecodeError::InvalidHeader)));
+}
+
+fn checksum_codec() -> (PacketEncoder, PacketDecoder) {
+    let mut encoder = PacketEncoder::new();
+    encoder.set_checksum(FrameChecksum::Crc32c);
+    let mut decoder = PacketDecoder::new(4096);
+    decoder.set_checksum(FrameChecksum::Crc32c);
+    (encoder, decoder)
+}
+
+#[test]
+fn checksum_round_trip() {
+    let (encoder, mut decoder) = checksum_codec();
+
+    let mut encoded = Vec::new();
+    encoder.encode(b"abc", &mut encoded).unwrap();
+    encoder.encode(b"", &mut encoded).unwrap();
+    assert_eq!(encoded.len(), 2 * (4 + CHECKSUM_SIZE) + 3);
+
+    for byte in encoded {
+        decoder.push(&[byte]).unwrap();
+    }
+    assert_eq!(decoder.next_packet().unwrap(), b"abc");
+    assert_eq!(decoder.next_packet().unwrap(), b"");
+    assert!(!decoder.has_partial_packet());
+}
+
+#[test]
+fn checksum_mismatch() {
+    let (encoder, mut decoder) = checksum_codec();
+
+    let mut encoded = Vec::new();
+    encoder.encode(b"abc", &mut encoded).unwrap();
+    encoded[5] ^= 0x01;
+
+    assert!(matches!(decoder.push(&encoded), Err(DecodeError::ChecksumMismatch)));
+    assert!(decoder.next_packet().is_none());
+}
+
+// collects the streamed payload and returns the data behind the packet
+fn stream_packet<'a>(decoder: &mut PacketDecoder, mut data: &'a [u8], payload: &mut Vec<u8>) -> Option<&'a [u8]> {
+    loop {
+        match decoder.next_stream_chunk(&mut data).unwrap() {
+            StreamChunk::Payload(chunk) => payload.extend_from_slice(chunk),
+            StreamChunk::RequiresData => return None,
+            StreamChunk::Finished => return Some(data),
+        }
+    }
+}
+
+#[test]
+fn stream_partially_buffered_packet() {
+    let (encoder, mut decoder) = checksum_codec();
+
+    let mut encoded = Vec::new();
+    encoder.encode(b"streamed payload", &mut encoded).unwrap();
+    encoder.encode(b"next", &mut encoded).unwrap();
+
+    decoder.push(&encoded[..10]).unwrap();
+    let mut payload = decoder.start_stream();
+    assert_eq!(payload, b"stream");
+
+    for byte in 10..encoded.len() {
+        if let Some(rest) = stream_packet(&mut decoder, &encoded[byte..byte + 1], &mut payload) {
+            decoder.push(rest).unwrap();
+            decoder.push(&encoded[byte + 1..]).unwrap();
+            break;
+        }
+    }
+    assert_eq!(payload, b"streamed payload");
+    assert_eq!(decoder.next_packet().unwrap(), b"next");
+}
+
+#[test]
+fn stream_checksum_mismatch() {
+    let (encoder, mut decoder) = checksum_codec();
+
+    let mut encoded = Vec::new();
+    encoder.encode(b"abc", &mut encoded).unwrap();
+    encoded[8] ^= 0x01;
+
+    let mut data = encoded.as_slice();
+    assert!(matches!(decoder.next_stream_chunk(&mut data), Ok(StreamChunk::Payload(b"abc"))));
+    assert!(matches!(decoder.next_stream_chunk(&mut data), Err(DecodeError::ChecksumMismatch)));
+}
+
+#[test]
+fn push_while_streaming() {
+    let mut decoder = PacketDecoder::new(4096);
+    let encoded = encode_all(&[b"abc"]);
+
+    let mut payload = Vec::new();
+    assert!(stream_packet(&mut decoder, &encoded[..5], &mut payload).is_none());
+    assert!(matches!(decoder.push(&encoded[5..]), Err(DecodeError::StreamInProgress)));
+}
@@ -0,0 +1,213 @@
+use std::{
+    net::{SocketAddr, TcpStream},
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use xs_rust_library::{
+    connection::Connection,
+    encrypted_connection::EncryptedConnection,
+    encryption::aes256_crypto::Aes256Crypto,
+    events::subscription::Subscription,
+    key_exchange::{curve25519::Curve25519, HandshakeMode},
+    packet_connection::PacketConnection,
+    packet_listener::{ListenerEvent, PacketListener, Session, ShutdownHandle},
+};
+
+fn echo<Con: Connection>(mut session: Session<Con>) {
+    while let Ok(packet) = session.connection().receive() {
+        if session.connection().send(&packet).is_err() {
+            return;
+        }
+    }
+}
+
+fn collect_events<Con: Send + 'static>(
+    listener: &mut PacketListener<Con>,
+) -> (mpsc::Receiver<ListenerEvent>, Subscription<ListenerEvent>) {
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let subscription = listener.subscribe(Box::new(move |event| sender.lock().unwrap().send(event.clone()).unwrap()));
+    (receiver, subscription)
+}
+
+fn start<Con: Connection + Send + 'static>(listener: PacketListener<Con>) -> (SocketAddr, ShutdownHandle, JoinHandle<()>) {
+    let address = listener.local_addr().unwrap();
+    let shutdown_handle = listener.shutdown_handle();
+    let join_handle = thread::spawn(move || listener.run(echo).unwrap());
+    (address, shutdown_handle, join_handle)
+}
+
+fn connect(address: SocketAddr) -> PacketConnection {
+    PacketConnection::new(TcpStream::connect(address).unwrap(), 1024)
+}
+
+#[test]
+fn echo_sessions() {
+    let mut listener = PacketListener::bind("127.0.0.1:0").unwrap().with_workers(4);
+    let (events, _subscription) = collect_events(&mut listener);
+    let (address, shutdown_handle, join_handle) = start(listener);
+
+    let mut clients: Vec<_> = (0..3).map(|_| connect(address)).collect();
+    for (i, client) in clients.iter_mut().enumerate() {
+        client.send(&[i as u8]).unwrap();
+    }
+    for (i, client) in clients.iter_mut().enumerate() {
+        assert_eq!(client.receive().unwrap(), [i as u8]);
+    }
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+    for client in &mut clients {
+        assert!(client.receive().is_err());
+    }
+
+    let events: Vec<_> = events.try_iter().collect();
+    assert_eq!(events.iter().filter(|e| matches!(e, ListenerEvent::Opened { .. })).count(), 3);
+    assert_eq!(events.iter().filter(|e| matches!(e, ListenerEvent::Closed { .. })).count(), 3);
+}
+
+#[test]
+fn max_connections() {
+    let mut listener = PacketListener::bind("127.0.0.1:0").unwrap().with_max_connections(1);
+    let (events, _subscription) = collect_events(&mut listener);
+    let (address, shutdown_handle, join_handle) = start(listener);
+
+    let mut first = connect(address);
+    first.send(b"test").unwrap();
+    assert_eq!(first.receive().unwrap(), b"test");
+
+    let mut second = connect(address);
+    assert!(second.receive().is_err());
+    assert!(matches!(events.recv_timeout(Duration::from_secs(5)).unwrap(), ListenerEvent::Opened { .. }));
+    assert!(matches!(events.recv_timeout(Duration::from_secs(5)).unwrap(), ListenerEvent::Rejected { .. }));
+
+    // a slot is free again after the first session was closed
+    drop(first);
+    assert!(matches!(events.recv_timeout(Duration::from_secs(5)).unwrap(), ListenerEvent::Closed { .. }));
+    let mut third = connect(address);
+    third.send(b"test").unwrap();
+    assert_eq!(third.receive().unwrap(), b"test");
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn encrypted_sessions() {
+    let mut listener =
+        PacketListener::<EncryptedConnection<Aes256Crypto, PacketConnection>>::bind_encrypted("127.0.0.1:0", Curve25519).unwrap();
+    let (events, _subscription) = collect_events(&mut listener);
+    let (address, shutdown_handle, join_handle) = start(listener);
+
+    let mut client = EncryptedConnection::<Aes256Crypto, _>::with_handshake(connect(address), Curve25519, HandshakeMode::Client).unwrap();
+    client.send(b"top secret").unwrap();
+    assert_eq!(client.receive().unwrap(), b"top secret");
+
+    // a client that closes the connection during the handshake
+    drop(connect(address));
+
+    assert!(matches!(events.recv_timeout(Duration::from_secs(5)).unwrap(), ListenerEvent::Opened { .. }));
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(5)).unwrap(),
+        ListenerEvent::HandshakeFailed { .. }
+    ));
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn handshake_timeout() {
+    let mut listener =
+        PacketListener::<EncryptedConnection<Aes256Crypto, PacketConnection>>::bind_encrypted("127.0.0.1:0", Curve25519)
+            .unwrap()
+            .with_workers(1)
+            .with_handshake_timeout(Some(Duration::from_millis(100)));
+    let (events, _subscription) = collect_events(&mut listener);
+    let (address, shutdown_handle, join_handle) = start(listener);
+
+    // a client that never starts the handshake must not occupy the only worker
+    let _silent = TcpStream::connect(address).unwrap();
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(5)).unwrap(),
+        ListenerEvent::HandshakeFailed { .. }
+    ));
+
+    let mut client = EncryptedConnection::<Aes256Crypto, _>::with_handshake(connect(address), Curve25519, HandshakeMode::Client).unwrap();
+    client.send(b"test").unwrap();
+    assert_eq!(client.receive().unwrap(), b"test");
+
+    // the timeout only applies to the handshake
+    thread::sleep(Duration::from_millis(200));
+    client.send(b"test").unwrap();
+    assert_eq!(client.receive().unwrap(), b"test");
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn panicking_handler() {
+    let mut listener = PacketListener::bind("127.0.0.1:0").unwrap().with_workers(1);
+    let (events, _subscription) = collect_events(&mut listener);
+    let address = listener.local_addr().unwrap();
+    let shutdown_handle = listener.shutdown_handle();
+    let join_handle = thread::spawn(move || {
+        listener
+            .run(|mut session: Session<PacketConnection>| {
+                if session.connection().receive().unwrap() == b"panic" {
+                    panic!("handler panicked");
+                }
+                echo(session);
+            })
+            .unwrap()
+    });
+
+    let mut first = connect(address);
+    first.send(b"panic").unwrap();
+    assert!(first.receive().is_err());
+
+    // the worker survived the panic
+    let mut second = connect(address);
+    second.send(b"hello").unwrap();
+    second.send(b"test").unwrap();
+    assert_eq!(second.receive().unwrap(), b"test");
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+    let events: Vec<_> = events.try_iter().collect();
+    assert_eq!(events.iter().filter(|e| matches!(e, ListenerEvent::Closed { .. })).count(), 2);
+}
+
+#[test]
+fn unsubscribe_from_handler() {
+    let mut listener = PacketListener::bind("127.0.0.1:0").unwrap();
+    let (events, _subscription) = collect_events(&mut listener);
+
+    // the handler drops its own subscription on the first event
+    let (sender, received) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let own_subscription = Arc::new(Mutex::new(None));
+    let handler_subscription = own_subscription.clone();
+    let subscription = listener.subscribe(Box::new(move |event| {
+        sender.lock().unwrap().send(event.clone()).unwrap();
+        handler_subscription.lock().unwrap().take();
+    }));
+    *own_subscription.lock().unwrap() = Some(subscription);
+    let (address, shutdown_handle, join_handle) = start(listener);
+
+    let mut client = connect(address);
+    client.send(b"test").unwrap();
+    assert_eq!(client.receive().unwrap(), b"test");
+    drop(client);
+    assert!(matches!(events.recv_timeout(Duration::from_secs(5)).unwrap(), ListenerEvent::Opened { .. }));
+    assert!(matches!(events.recv_timeout(Duration::from_secs(5)).unwrap(), ListenerEvent::Closed { .. }));
+
+    assert!(matches!(received.try_recv().unwrap(), ListenerEvent::Opened { .. }));
+    assert!(received.try_recv().is_err());
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
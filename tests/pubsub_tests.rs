@@ -0,0 +1,244 @@
+use std::{
+    net::{SocketAddr, TcpStream},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use xs_rust_library::{
+    connection::Connection,
+    encrypted_connection::EncryptedConnection,
+    encryption::aes256_crypto::Aes256Crypto,
+    key_exchange::{curve25519::Curve25519, HandshakeMode},
+    packet_connection::PacketConnection,
+    packet_listener::{PacketListener, ShutdownHandle},
+    pubsub::{
+        hub::Hub,
+        hub_client::{HubClient, TopicMessage},
+        hub_message::HubMessage,
+        send_queue::SlowConsumerPolicy,
+        Error,
+    },
+};
+
+fn start_hub(hub: &Hub) -> (SocketAddr, ShutdownHandle, JoinHandle<()>) {
+    let listener = PacketListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let shutdown_handle = listener.shutdown_handle();
+    let hub = hub.clone();
+    let join_handle = thread::spawn(move || hub.serve(listener).unwrap());
+    (address, shutdown_handle, join_handle)
+}
+
+fn connect(address: SocketAddr) -> HubClient<PacketConnection> {
+    HubClient::new(PacketConnection::new(TcpStream::connect(address).unwrap(), 1024))
+}
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(5), "condition not met in time");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn message(topic: &str, payload: &[u8]) -> TopicMessage {
+    TopicMessage {
+        topic: topic.to_string(),
+        payload: payload.to_vec(),
+    }
+}
+
+#[test]
+fn message_round_trip() {
+    let messages = [
+        HubMessage::Subscribe { topic: "temperature" },
+        HubMessage::Unsubscribe { topic: "" },
+        HubMessage::Publish {
+            topic: "temperature",
+            payload: b"21.5",
+        },
+        HubMessage::Message {
+            topic: "humidity",
+            payload: b"",
+        },
+    ];
+    for message in messages {
+        let mut encoded = Vec::new();
+        message.encode(&mut encoded);
+        assert_eq!(HubMessage::parse(&encoded).unwrap(), message);
+    }
+
+    assert!(HubMessage::parse(&[0, 5, 0, b'a']).is_none());
+    assert!(HubMessage::parse(&[0, 1, 0, b'a', b'b']).is_none());
+    assert!(HubMessage::parse(&[9, 0, 0]).is_none());
+}
+
+#[test]
+fn fan_out() {
+    let hub = Hub::new();
+    let (address, shutdown_handle, join_handle) = start_hub(&hub);
+
+    let mut first = connect(address);
+    let mut second = connect(address);
+    let mut other = connect(address);
+    first.subscribe("temperature").unwrap();
+    second.subscribe("temperature").unwrap();
+    other.subscribe("humidity").unwrap();
+    wait_until(|| hub.subscribers("temperature") == 2 && hub.subscribers("humidity") == 1);
+    assert_eq!(hub.clients(), 3);
+
+    other.publish("temperature", b"21.5").unwrap();
+    assert_eq!(first.receive().unwrap(), message("temperature", b"21.5"));
+    assert_eq!(second.receive().unwrap(), message("temperature", b"21.5"));
+
+    assert_eq!(hub.publish("humidity", b"40").unwrap(), 1);
+    assert_eq!(other.receive().unwrap(), message("humidity", b"40"));
+    assert!(first.receive_timeout(Duration::from_millis(20)).unwrap().is_none());
+
+    second.unsubscribe("temperature").unwrap();
+    wait_until(|| hub.subscribers("temperature") == 1);
+    first.publish("temperature", b"22").unwrap();
+    assert_eq!(first.receive().unwrap(), message("temperature", b"22"));
+    assert!(second.receive_timeout(Duration::from_millis(20)).unwrap().is_none());
+
+    drop(first);
+    wait_until(|| hub.subscribers("temperature") == 0 && hub.clients() == 2);
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+    assert!(second.receive().is_err());
+}
+
+#[test]
+fn encrypted_clients() {
+    let hub = Hub::new();
+    let listener =
+        PacketListener::<EncryptedConnection<Aes256Crypto, PacketConnection>>::bind_encrypted("127.0.0.1:0", Curve25519).unwrap();
+    let address = listener.local_addr().unwrap();
+    let shutdown_handle = listener.shutdown_handle();
+    let join_handle = {
+        let hub = hub.clone();
+        thread::spawn(move || hub.serve(listener).unwrap())
+    };
+
+    let connection = PacketConnection::new(TcpStream::connect(address).unwrap(), 1024);
+    let connection = EncryptedConnection::<Aes256Crypto, _>::with_handshake(connection, Curve25519, HandshakeMode::Client).unwrap();
+    let mut client = HubClient::new(connection);
+    client.subscribe("secrets").unwrap();
+    wait_until(|| hub.subscribers("secrets") == 1);
+
+    client.publish("secrets", b"top secret").unwrap();
+    assert_eq!(client.receive().unwrap(), message("secrets", b"top secret"));
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn unsubscribed_topics_are_removed() {
+    let hub = Hub::new();
+    let (address, shutdown_handle, join_handle) = start_hub(&hub);
+
+    let mut client = connect(address);
+    for i in 0..100 {
+        let topic = format!("topic {i}");
+        client.subscribe(&topic).unwrap();
+        client.unsubscribe(&topic).unwrap();
+    }
+    // messages are handled in order, so all topics were unsubscribed once this one shows up
+    client.subscribe("kept").unwrap();
+    wait_until(|| hub.subscribers("kept") == 1);
+    assert_eq!(hub.topics(), 1);
+
+    drop(client);
+    wait_until(|| hub.clients() == 0);
+    assert_eq!(hub.topics(), 0);
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+// publishes until the client falls behind, returns the number of published packets
+fn overwhelm(hub: &Hub, condition: impl Fn() -> bool) -> u32 {
+    let payload = vec![0; 64 * 1024];
+    let mut published = 0;
+    while !condition() {
+        hub.publish("flood", &payload).unwrap();
+        published += 1;
+        assert!(published < 100_000, "client never fell behind");
+    }
+    published
+}
+
+#[test]
+fn slow_consumer_drop_oldest() {
+    let hub = Hub::new().with_queue_capacity(4);
+    let (address, shutdown_handle, join_handle) = start_hub(&hub);
+
+    let mut client = connect(address);
+    client.subscribe("flood").unwrap();
+    wait_until(|| hub.subscribers("flood") == 1);
+
+    let published = overwhelm(&hub, || hub.dropped_packets() > 0);
+    hub.publish("flood", b"last").unwrap();
+
+    let mut received = 0;
+    loop {
+        let message = client.receive().unwrap();
+        if message.payload == b"last" {
+            break;
+        }
+        received += 1;
+    }
+    assert!(received < published);
+    assert_eq!(hub.clients(), 1);
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn slow_consumer_disconnect() {
+    let hub = Hub::new().with_queue_capacity(4).with_slow_consumer_policy(SlowConsumerPolicy::Disconnect);
+    let (address, shutdown_handle, join_handle) = start_hub(&hub);
+
+    let mut client = connect(address);
+    client.subscribe("flood").unwrap();
+    wait_until(|| hub.subscribers("flood") == 1);
+
+    overwhelm(&hub, || hub.subscribers("flood") == 0);
+    wait_until(|| hub.clients() == 0);
+    while client.receive().is_ok() {}
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn invalid_message() {
+    let hub = Hub::new();
+    let (address, shutdown_handle, join_handle) = start_hub(&hub);
+
+    let mut client = connect(address);
+    client.get_underlying_connection().send(b"garbage").unwrap();
+    assert!(client.receive().is_err());
+    wait_until(|| hub.clients() == 0);
+
+    assert!(matches!(
+        client.subscribe(&"a".repeat(70_000)),
+        Err(Error::TopicTooLong { len: 70_000 })
+    ));
+    assert!(matches!(
+        hub.publish(&"a".repeat(70_000), b"test"),
+        Err(Error::TopicTooLong { len: 70_000 })
+    ));
+
+    shutdown_handle.shutdown();
+    join_handle.join().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "a send queue needs room for at least one packet")]
+fn zero_queue_capacity() {
+    let _ = Hub::new().with_queue_capacity(0);
+}
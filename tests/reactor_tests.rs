@@ -0,0 +1,212 @@
+#![cfg(feature = "reactor")]
+
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use xs_rust_library::{
+    connection::Connection,
+    packet_connection::PacketConnection,
+    reactor::{ConnectionClosed, ConnectionId, Error, Reactor},
+};
+
+// returns the client side connections and the accepted server side streams
+fn connect_clients(count: usize) -> (Vec<PacketConnection>, Vec<TcpStream>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    (0..count)
+        .map(|_| {
+            let client = PacketConnection::new(TcpStream::connect(address).unwrap(), 1024);
+            (client, listener.accept().unwrap().0)
+        })
+        .unzip()
+}
+
+fn collect_closed(reactor: &mut Reactor) -> (mpsc::Receiver<ConnectionClosed>, impl Sized) {
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let subscription = reactor.subscribe_closed(Box::new(move |closed| sender.lock().unwrap().send(closed.clone()).unwrap()));
+    (receiver, subscription)
+}
+
+#[test]
+fn echo_many_connections() {
+    let (mut clients, streams) = connect_clients(100);
+
+    let mut reactor = Reactor::new().unwrap();
+    let handle = reactor.handle();
+    let mut subscriptions = Vec::new();
+    for stream in streams {
+        let id = reactor.register(stream).unwrap();
+        let handle = handle.clone();
+        subscriptions.push(reactor.subscribe(id, Box::new(move |packet| handle.send(id, packet).unwrap())).unwrap());
+    }
+    assert_eq!(reactor.connections(), 100);
+    let join_handle = thread::spawn(move || reactor.run().unwrap());
+
+    for round in 0..3_u8 {
+        for (i, client) in clients.iter_mut().enumerate() {
+            client.send(&[round, i as u8]).unwrap();
+            client.send(&vec![i as u8; 10_000]).unwrap();
+        }
+        for (i, client) in clients.iter_mut().enumerate() {
+            assert_eq!(client.receive().unwrap(), [round, i as u8]);
+            assert_eq!(client.receive().unwrap(), vec![i as u8; 10_000]);
+        }
+    }
+
+    handle.stop().unwrap();
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn closed_connections() {
+    let (mut clients, mut streams) = connect_clients(2);
+
+    let mut reactor = Reactor::new().unwrap().with_max_packet_size(16);
+    let (closed, _subscription) = collect_closed(&mut reactor);
+    let first = reactor.register(streams.remove(0)).unwrap();
+    let second = reactor.register(streams.remove(0)).unwrap();
+
+    drop(clients.remove(0));
+    clients[0].send(&[0; 17]).unwrap();
+    while reactor.connections() > 0 {
+        reactor.poll(Some(Duration::from_secs(5))).unwrap();
+    }
+
+    let mut closed: Vec<_> = closed.try_iter().collect();
+    closed.sort_by_key(|closed| closed.id != first);
+    assert_eq!(closed[0], ConnectionClosed { id: first, error: None });
+    assert_eq!(closed[1].id, second);
+    assert!(closed[1].error.is_some());
+
+    assert!(matches!(reactor.send(first, b"test"), Err(Error::UnknownConnection(id)) if id == first));
+    assert!(clients[0].receive().is_err());
+}
+
+#[test]
+fn close_after_sending() {
+    let (mut clients, mut streams) = connect_clients(1);
+
+    let mut reactor = Reactor::new().unwrap();
+    let handle = reactor.handle();
+    let id: ConnectionId = reactor.register(streams.remove(0)).unwrap();
+    let join_handle = thread::spawn(move || {
+        while reactor.connections() > 0 {
+            reactor.poll(Some(Duration::from_secs(5))).unwrap();
+        }
+    });
+
+    // bigger than the socket buffers, so the reactor has to wait for writable readiness
+    handle.send(id, &vec![7; 8 * 1024 * 1024]).unwrap();
+    handle.close(id).unwrap();
+
+    assert_eq!(clients[0].receive().unwrap(), vec![7; 8 * 1024 * 1024]);
+    assert!(clients[0].receive().is_err());
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn packets_before_invalid_data_are_dispatched() {
+    let (mut clients, mut streams) = connect_clients(1);
+
+    let mut reactor = Reactor::new().unwrap().with_max_packet_size(16);
+    let (closed, _closed_subscription) = collect_closed(&mut reactor);
+    let id = reactor.register(streams.remove(0)).unwrap();
+    let (sender, received) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let _subscription = reactor
+        .subscribe(id, Box::new(move |packet| sender.lock().unwrap().send(packet.clone()).unwrap()))
+        .unwrap();
+
+    // the valid packet is dispatched even if both arrive with the same read
+    clients[0].send(b"valid").unwrap();
+    clients[0].send(&[0; 17]).unwrap();
+    while reactor.connections() > 0 {
+        reactor.poll(Some(Duration::from_secs(5))).unwrap();
+    }
+
+    assert_eq!(received.try_recv().unwrap(), b"valid");
+    assert!(closed.try_recv().unwrap().error.is_some());
+}
+
+#[test]
+fn write_buffer_full() {
+    let (mut clients, mut streams) = connect_clients(2);
+
+    let mut reactor = Reactor::new().unwrap().with_max_write_buffer_size(1024 * 1024);
+    let (closed, _subscription) = collect_closed(&mut reactor);
+    let first = reactor.register(streams.remove(0)).unwrap();
+    let second = reactor.register(streams.remove(0)).unwrap();
+
+    // the clients do not read, so the socket buffers fill up and the rest is queued
+    let packet = vec![1; 256 * 1024];
+    let mut result = Ok(());
+    for _ in 0..256 {
+        result = reactor.send(first, &packet);
+        if result.is_err() {
+            break;
+        }
+    }
+    assert!(matches!(result, Err(Error::WriteBufferFull { id, .. }) if id == first));
+    assert_eq!(reactor.connections(), 2);
+
+    // commands can't report the error, the connection is closed instead
+    let handle = reactor.handle();
+    for _ in 0..256 {
+        handle.send(second, &packet).unwrap();
+    }
+    reactor.poll(Some(Duration::ZERO)).unwrap();
+    let closed = closed.try_recv().unwrap();
+    assert_eq!(closed.id, second);
+    assert!(closed.error.is_some());
+    assert_eq!(reactor.connections(), 1);
+
+    assert_eq!(clients[0].receive().unwrap(), packet);
+}
+
+#[test]
+fn fast_remote_does_not_starve_others() {
+    const PACKET_COUNT: usize = 4096;
+    let (clients, mut streams) = connect_clients(2);
+    let mut clients = clients.into_iter();
+    let mut fast = clients.next().unwrap();
+    let mut slow = clients.next().unwrap();
+
+    let mut reactor = Reactor::new().unwrap();
+    let fast_id = reactor.register(streams.remove(0)).unwrap();
+    let slow_id = reactor.register(streams.remove(0)).unwrap();
+    let (sender, received) = mpsc::channel();
+    let mut subscriptions = Vec::new();
+    for id in [fast_id, slow_id] {
+        let sender = Mutex::new(sender.clone());
+        subscriptions.push(reactor.subscribe(id, Box::new(move |_| sender.lock().unwrap().send(id).unwrap())).unwrap());
+    }
+
+    let join_handle = thread::spawn(move || {
+        for _ in 0..PACKET_COUNT {
+            fast.send(&[0; 1024]).unwrap();
+        }
+        fast
+    });
+    thread::sleep(Duration::from_millis(100));
+    slow.send(b"test").unwrap();
+
+    let mut fast_packets = 0;
+    let mut slow_packets = 0;
+    while fast_packets < PACKET_COUNT || slow_packets < 1 {
+        // a turn reads at most 1MiB from a connection, no more than 1024 packets of this size
+        assert!(reactor.poll(Some(Duration::from_secs(5))).unwrap() <= 1024 + 1);
+        for id in received.try_iter() {
+            match id == fast_id {
+                true => fast_packets += 1,
+                false => slow_packets += 1,
+            }
+        }
+    }
+    assert_eq!(fast_packets, PACKET_COUNT);
+    join_handle.join().unwrap();
+}
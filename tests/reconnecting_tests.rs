@@ -0,0 +1,129 @@
+mod util;
+
+use std::{
+    net::TcpListener,
+    sync::{mpsc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use util::test_connections::ChannelConnection;
+use xs_rust_library::{
+    connection::{Connection, TimedReceive},
+    encrypted_connection::EncryptedConnection,
+    encryption::aes256_crypto::Aes256Crypto,
+    key_exchange::{curve25519::Curve25519, HandshakeMode},
+    packet_connection::PacketConnection,
+    reconnecting_connection::{backoff::Backoff, Error, LinkEvent, ReconnectingConnection},
+};
+
+fn collect_events<Con, E>(connection: &mut ReconnectingConnection<Con>) -> (mpsc::Receiver<LinkEvent>, impl Sized)
+where
+    Con: Connection<ErrorType = E>,
+    E: std::fmt::Display,
+{
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let subscription = connection.subscribe(Box::new(move |event| sender.lock().unwrap().send(event.clone()).unwrap()));
+    (receiver, subscription)
+}
+
+#[test]
+fn backoff_delays() {
+    let initial = Duration::from_millis(100);
+    let mut backoff = Backoff::new(initial, Duration::from_millis(500));
+
+    for expected in [100, 200, 400, 500, 500] {
+        let delay = backoff.next_delay();
+        let expected = Duration::from_millis(expected);
+        assert!(delay <= expected && delay >= expected / 2, "{delay:?} not within {expected:?}");
+    }
+
+    backoff.reset();
+    assert!(backoff.next_delay() <= initial);
+}
+
+#[test]
+fn reconnect_after_disconnect() {
+    let listener = TcpListener::bind("127.0.0.1:7015").unwrap();
+    let join_handle = thread::spawn(move || {
+        let mut first = PacketConnection::new(listener.accept().unwrap().0, 1024);
+        assert_eq!(first.receive().unwrap(), b"first");
+        drop(first);
+
+        let mut second = PacketConnection::new(listener.accept().unwrap().0, 1024);
+        second.send(b"welcome back").unwrap();
+        second.receive().unwrap()
+    });
+
+    let mut connection = ReconnectingConnection::tcp("127.0.0.1:7015").with_backoff(Duration::from_millis(1), Duration::from_millis(10));
+    let (events, _subscription) = collect_events(&mut connection);
+    assert!(!connection.is_connected());
+
+    connection.send(b"first").unwrap();
+    assert!(connection.is_connected());
+    assert_eq!(connection.receive().unwrap(), b"welcome back");
+    connection.send(b"second").unwrap();
+    assert_eq!(join_handle.join().unwrap(), b"second");
+
+    assert_eq!(events.try_recv().unwrap(), LinkEvent::Connected);
+    assert!(matches!(events.try_recv().unwrap(), LinkEvent::Disconnected { .. }));
+    assert_eq!(events.try_recv().unwrap(), LinkEvent::Connected);
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn encrypted_reconnect() {
+    let listener = TcpListener::bind("127.0.0.1:7016").unwrap();
+    let join_handle = thread::spawn(move || {
+        for i in 0..2_u8 {
+            let connection = PacketConnection::new(listener.accept().unwrap().0, 1024);
+            let mut connection =
+                EncryptedConnection::<Aes256Crypto, _>::with_handshake(connection, Curve25519, HandshakeMode::Server).unwrap();
+            assert_eq!(connection.receive().unwrap(), b"ping");
+            connection.send(&[i]).unwrap();
+        }
+    });
+
+    let mut connection = ReconnectingConnection::<EncryptedConnection<Aes256Crypto, _>>::encrypted_tcp("127.0.0.1:7016", Curve25519)
+        .with_backoff(Duration::from_millis(1), Duration::from_millis(10));
+
+    connection.send(b"ping").unwrap();
+    assert_eq!(connection.receive().unwrap(), [0]);
+    // the server closed the first session, the next receive fails and the connection is replaced
+    assert!(connection.receive_timeout(Duration::from_millis(100)).unwrap().is_none());
+    connection.send(b"ping").unwrap();
+    assert_eq!(connection.receive().unwrap(), [1]);
+    join_handle.join().unwrap();
+}
+
+#[test]
+fn give_up_after_max_attempts() {
+    let mut attempts = 0;
+    let mut connection = ReconnectingConnection::new(move || -> Result<ChannelConnection, String> {
+        attempts += 1;
+        Err(format!("attempt {attempts} refused"))
+    })
+    .with_backoff(Duration::from_millis(1), Duration::from_millis(5))
+    .with_max_attempts(3);
+
+    match connection.send(b"test") {
+        Err(Error::ConnectFailed { attempts, reason }) => {
+            assert_eq!(attempts, 3);
+            assert_eq!(reason, "attempt 3 refused");
+        }
+        _ => panic!("connecting did not give up"),
+    }
+    assert!(matches!(connection.connect(), Err(Error::ConnectFailed { attempts: 3, .. })));
+}
+
+#[test]
+fn receive_timeout_while_disconnected() {
+    let mut connection = ReconnectingConnection::new(|| -> Result<ChannelConnection, String> { Err("refused".into()) })
+        .with_backoff(Duration::from_secs(10), Duration::from_secs(10));
+
+    let start = Instant::now();
+    assert!(connection.receive_timeout(Duration::from_millis(50)).unwrap().is_none());
+    assert!(connection.try_receive().unwrap().is_none());
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,117 @@
+use std::{fs, time::Duration};
+
+use xs_rust_library::{
+    connection::{Connection, TimedReceive},
+    memory_connection::MemoryConnection,
+    recording_connection::{
+        capture::{self, CaptureReader, CaptureRecord, CaptureWriter, Direction},
+        RecordingConnection,
+    },
+    replay_connection::{Error, ReplayConnection},
+};
+
+fn record_session() -> Vec<u8> {
+    let (local, mut remote) = MemoryConnection::pair();
+    let mut local = RecordingConnection::new(local, Vec::new()).unwrap();
+
+    remote.send(b"hello").unwrap();
+    assert_eq!(local.receive().unwrap(), b"hello");
+    local.send(b"reply").unwrap();
+    assert!(local.try_receive().unwrap().is_none());
+    remote.send(&[7; 300]).unwrap();
+    assert_eq!(local.receive_timeout(Duration::from_secs(1)).unwrap().unwrap(), [7; 300]);
+    local.send(b"bye").unwrap();
+
+    local.into_inner().unwrap().1
+}
+
+#[test]
+fn record_and_replay() {
+    let path = std::env::temp_dir().join(format!("xs_recording_test_{}.cap", std::process::id()));
+    let (local, mut remote) = MemoryConnection::pair();
+    let mut local = RecordingConnection::create(local, &path).unwrap();
+    remote.send(b"hello").unwrap();
+    assert_eq!(local.receive().unwrap(), b"hello");
+    local.send(b"reply").unwrap();
+    local.flush().unwrap();
+
+    let mut replay = ReplayConnection::open(&path).unwrap().with_send_verification();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.receive().unwrap(), b"hello");
+    replay.send(b"reply").unwrap();
+    assert!(replay.is_finished());
+    assert!(matches!(replay.receive(), Err(Error::EndOfCapture)));
+}
+
+#[test]
+fn capture_format() {
+    let capture = record_session();
+    // header, 4 records with direction, time delta and length, packet data
+    assert!(capture.len() <= 5 + 4 * 3 + 10 + 2 + 8 + 300);
+
+    let records = CaptureReader::new(capture.as_slice()).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    let directions: Vec<_> = records.iter().map(|record| record.direction).collect();
+    assert_eq!(directions, [Direction::Received, Direction::Sent, Direction::Received, Direction::Sent]);
+    assert_eq!(records[2].data, [7; 300]);
+    assert!(records.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write(Direction::Sent, Duration::from_secs(3600), b"late").unwrap();
+    let capture = writer.into_inner();
+    let mut reader = CaptureReader::new(capture.as_slice()).unwrap();
+    assert_eq!(
+        reader.read().unwrap().unwrap(),
+        CaptureRecord {
+            direction: Direction::Sent,
+            timestamp: Duration::from_secs(3600),
+            data: b"late".to_vec(),
+        }
+    );
+    assert!(reader.read().unwrap().is_none());
+}
+
+#[test]
+fn send_verification() {
+    let capture = record_session();
+
+    let mut replay = ReplayConnection::new(capture.as_slice()).unwrap();
+    replay.send(b"anything").unwrap();
+    assert_eq!(replay.remaining_sent(), 1);
+    assert_eq!(replay.remaining_received(), 2);
+
+    let mut replay = ReplayConnection::new(capture.as_slice()).unwrap().with_send_verification();
+    replay.send(b"reply").unwrap();
+    assert!(matches!(replay.send(b"wrong"), Err(Error::SendMismatch { index: 1 })));
+    assert!(matches!(replay.send(b"bye"), Err(Error::UnexpectedSend)));
+    assert_eq!(replay.receive().unwrap(), b"hello");
+    assert_eq!(replay.try_receive().unwrap().unwrap(), [7; 300]);
+    assert!(matches!(replay.try_receive(), Err(Error::EndOfCapture)));
+}
+
+#[test]
+fn original_timing() {
+    let record = |millis, data: &[u8]| CaptureRecord {
+        direction: Direction::Received,
+        timestamp: Duration::from_millis(millis),
+        data: data.to_vec(),
+    };
+    let mut replay = ReplayConnection::from_records([record(0, b"first"), record(100, b"second")]).with_original_timing();
+
+    assert_eq!(replay.try_receive().unwrap().unwrap(), b"first");
+    assert!(replay.try_receive().unwrap().is_none());
+    assert!(replay.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+    assert_eq!(replay.receive_timeout(Duration::from_secs(1)).unwrap().unwrap(), b"second");
+}
+
+#[test]
+fn invalid_capture() {
+    assert!(matches!(ReplayConnection::new(&b"XS"[..]), Err(Error::Capture(capture::Error::InvalidHeader))));
+    assert!(matches!(
+        ReplayConnection::new(&b"XSRC\x09"[..]),
+        Err(Error::Capture(capture::Error::UnsupportedVersion { version: 9 }))
+    ));
+
+    let mut capture = record_session();
+    capture.truncate(capture.len() - 1);
+    assert!(matches!(ReplayConnection::new(capture.as_slice()), Err(Error::Capture(capture::Error::InvalidRecord))));
+}
@@ -0,0 +1,172 @@
+mod util;
+
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use xs_rust_library::{
+    chaos_connection::{chaos_config::ChaosConfig, ChaosConnection},
+    connection::{Connection, TimedReceive},
+    memory_connection::MemoryConnection,
+    reliable_connection::{reliability::Reliability, rto_estimator::RtoEstimator, segment::Segment, Error, ReliableConnection},
+};
+
+use crate::util::test_connections::ChannelConnection;
+
+fn new_lossy_pair(drop_probability: f64) -> (ChaosConnection<MemoryConnection>, ChaosConnection<MemoryConnection>) {
+    let (local, remote) = MemoryConnection::pair();
+    (
+        ChaosConnection::new(local, ChaosConfig::new(1).with_drop_probability(drop_probability)),
+        ChaosConnection::new(remote, ChaosConfig::new(2).with_drop_probability(drop_probability)),
+    )
+}
+
+#[test]
+fn segment_round_trip() {
+    let mut encoded = Vec::new();
+    Segment::Data {
+        sequence: 7,
+        ack: 3,
+        payload: b"abc",
+    }
+    .encode(&mut encoded);
+    assert_eq!(
+        Segment::parse(&encoded),
+        Some(Segment::Data {
+            sequence: 7,
+            ack: 3,
+            payload: b"abc"
+        })
+    );
+
+    encoded.clear();
+    Segment::Ack { ack: 1, selective: 0b101 }.encode(&mut encoded);
+    assert_eq!(Segment::parse(&encoded), Some(Segment::Ack { ack: 1, selective: 0b101 }));
+    assert_eq!(Segment::parse(&encoded[..10]), None);
+}
+
+#[test]
+fn rto_estimation() {
+    let mut estimator = RtoEstimator::new();
+    estimator.sample(Duration::from_millis(100));
+    assert_eq!(estimator.smoothed_rtt(), Some(Duration::from_millis(100)));
+    assert_eq!(estimator.rto(), Duration::from_millis(300));
+
+    estimator.backoff();
+    assert_eq!(estimator.rto(), Duration::from_millis(600));
+
+    estimator.sample(Duration::from_millis(100));
+    assert_eq!(estimator.rto(), Duration::from_millis(500));
+    estimator.reset_backoff();
+    assert_eq!(estimator.rto(), Duration::from_millis(250));
+}
+
+#[test]
+fn reorder_and_selective_ack() {
+    let now = Instant::now();
+    let mut sender = Reliability::new();
+    let mut receiver = Reliability::new();
+
+    for packet in [b"a", b"b", b"c"] {
+        sender.send(packet, now);
+    }
+    let segments: Vec<Vec<u8>> = std::iter::from_fn(|| sender.next_outgoing()).collect();
+
+    // the first segment is lost, the others arrive in reverse order
+    receiver.handle_segment(&segments[2], now);
+    receiver.handle_segment(&segments[1], now);
+    assert!(receiver.next_packet().is_none());
+    while let Some(ack) = receiver.next_outgoing() {
+        sender.handle_segment(&ack, now);
+    }
+    assert_eq!(sender.in_flight(), 1);
+
+    // only the lost segment is retransmitted
+    sender.poll(now + sender.rto()).unwrap();
+    let retransmitted = sender.next_outgoing().unwrap();
+    assert!(sender.next_outgoing().is_none());
+    assert_eq!(retransmitted, segments[0]);
+
+    receiver.handle_segment(&retransmitted, now);
+    let packets: Vec<Vec<u8>> = std::iter::from_fn(|| receiver.next_packet()).collect();
+    assert_eq!(packets, vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+}
+
+#[test]
+fn bogus_acks_are_ignored() {
+    let now = Instant::now();
+    let mut sender = Reliability::new();
+    sender.send(b"a", now);
+    sender.send(b"b", now);
+
+    // acks for sequences that were never sent must neither overflow nor acknowledge anything
+    for (ack, selective) in [(u64::MAX, u64::MAX), (u64::MAX - 1, 1), (3, 0)] {
+        let mut segment = Vec::new();
+        Segment::Ack { ack, selective }.encode(&mut segment);
+        sender.handle_segment(&segment, now);
+    }
+    assert_eq!(sender.in_flight(), 2);
+
+    let mut segment = Vec::new();
+    Segment::Ack { ack: 2, selective: u64::MAX }.encode(&mut segment);
+    sender.handle_segment(&segment, now);
+    assert_eq!(sender.in_flight(), 0);
+}
+
+#[test]
+fn reliable_connection_over_lossy_link() {
+    const PACKET_COUNT: usize = 200;
+    let (local_con, remote_con) = new_lossy_pair(0.25);
+    let (done_sender, done_receiver) = mpsc::channel();
+
+    let join_handle = thread::spawn(move || {
+        let mut remote_con = ReliableConnection::new(remote_con);
+        for _ in 0..PACKET_COUNT {
+            let packet = remote_con.receive().unwrap();
+            remote_con.send(&packet).unwrap();
+        }
+        remote_con.flush().unwrap();
+        done_sender.send(()).unwrap();
+        // a dropped remote would make the acknowledgements of the local connection fail
+        remote_con
+    });
+
+    let mut local_con = ReliableConnection::new(local_con);
+    for i in 0..PACKET_COUNT {
+        local_con.send(&vec![i as u8; i * 10]).unwrap();
+    }
+    for i in 0..PACKET_COUNT {
+        assert_eq!(local_con.receive().unwrap(), vec![i as u8; i * 10]);
+    }
+
+    // keep acknowledging until the remote received all acknowledgements
+    while done_receiver.try_recv().is_err() {
+        local_con.receive_timeout(Duration::from_millis(5)).unwrap();
+    }
+    let _remote_con = join_handle.join().unwrap();
+}
+
+#[test]
+fn reliable_receive_timeout() {
+    let (local_con, remote_con) = ChannelConnection::new_test_pair();
+    let mut local_con = ReliableConnection::new(local_con);
+    let mut remote_con = ReliableConnection::new(remote_con);
+
+    assert!(local_con.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+    assert!(local_con.try_receive().unwrap().is_none());
+
+    remote_con.send(b"test123").unwrap();
+    assert_eq!(local_con.receive_timeout(Duration::from_secs(5)).unwrap().unwrap(), b"test123");
+    remote_con.flush().unwrap();
+}
+
+#[test]
+fn reliable_peer_unreachable() {
+    let (local_con, _remote_con) = new_lossy_pair(1.0);
+    let mut local_con = ReliableConnection::new(local_con).with_max_retransmissions(2);
+
+    local_con.send(b"lost").unwrap();
+    assert!(matches!(local_con.flush(), Err(Error::PeerUnreachable { retransmissions: 2 })));
+}
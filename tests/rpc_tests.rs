@@ -0,0 +1,137 @@
+mod util;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use xs_rust_library::rpc::{
+    rpc_client::RpcClient,
+    rpc_message::RpcMessage,
+    rpc_server::{RpcCall, RpcServer, StopHandle},
+    Error, MethodId,
+};
+
+use crate::util::test_connections::ChannelConnection;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn start_server(
+    connection: ChannelConnection,
+    call_counter: Arc<AtomicUsize>,
+) -> (StopHandle, thread::JoinHandle<Result<(), Error>>) {
+    let mut server = RpcServer::new(connection);
+    server.register("echo", |payload| Ok(payload.to_vec()));
+    server.register(7, |payload| Ok(payload.iter().rev().copied().collect()));
+    server.register("fail", |_| Err("does not work".to_string()));
+    server.register("sleep", |payload| {
+        thread::sleep(Duration::from_millis(payload[0] as u64));
+        Ok(payload.to_vec())
+    });
+
+    let stop_handle = server.stop_handle();
+    let join_handle = thread::spawn(move || {
+        let _subscription = server.subscribe(Box::new(move |_: &RpcCall| {
+            call_counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        server.run()
+    });
+    (stop_handle, join_handle)
+}
+
+#[test]
+fn rpc_message_round_trip() {
+    let messages = [
+        RpcMessage::Request {
+            call_id: 1,
+            method: MethodId::Numeric(42),
+            payload: b"abc",
+        },
+        RpcMessage::Request {
+            call_id: 2,
+            method: MethodId::Name("echo".to_string()),
+            payload: b"",
+        },
+        RpcMessage::Response {
+            call_id: 3,
+            payload: b"result",
+        },
+        RpcMessage::ErrorResponse {
+            call_id: 4,
+            message: "failed",
+        },
+    ];
+
+    for message in messages {
+        let mut encoded = Vec::new();
+        message.encode(&mut encoded);
+        assert_eq!(RpcMessage::parse(&encoded), Some(message));
+    }
+    assert_eq!(RpcMessage::parse(&[0, 1, 2]), None);
+}
+
+#[test]
+fn rpc_calls() {
+    let (local_con, remote_con) = ChannelConnection::new_test_pair();
+    let call_counter = Arc::new(AtomicUsize::new(0));
+    let (stop_handle, join_handle) = start_server(remote_con, call_counter.clone());
+    let client = RpcClient::new(local_con);
+
+    assert_eq!(client.call("echo", b"hello", TIMEOUT).unwrap(), b"hello");
+    assert_eq!(client.call(7, b"abc", TIMEOUT).unwrap(), b"cba");
+    assert!(matches!(client.call("fail", b"", TIMEOUT), Err(Error::Remote(message)) if message == "does not work"));
+    assert!(matches!(client.call("missing", b"", TIMEOUT), Err(Error::Remote(_))));
+    assert_eq!(call_counter.load(Ordering::SeqCst), 4);
+
+    stop_handle.stop();
+    join_handle.join().unwrap().unwrap();
+}
+
+#[test]
+fn rpc_outstanding_calls() {
+    let (local_con, remote_con) = ChannelConnection::new_test_pair();
+    let (stop_handle, join_handle) = start_server(remote_con, Arc::new(AtomicUsize::new(0)));
+    let client = RpcClient::new(local_con);
+
+    let calls: Vec<_> = (0..10_u8).map(|i| client.start_call("echo", &[i]).unwrap()).collect();
+    for (i, call) in calls.into_iter().enumerate().rev() {
+        assert_eq!(call.wait(TIMEOUT).unwrap(), [i as u8]);
+    }
+
+    let threads: Vec<_> = (0..4_u8)
+        .map(|t| {
+            let client = client.clone();
+            thread::spawn(move || {
+                for i in 0..20_u8 {
+                    assert_eq!(client.call(7, &[t, i], TIMEOUT).unwrap(), [i, t]);
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    stop_handle.stop();
+    join_handle.join().unwrap().unwrap();
+}
+
+#[test]
+fn rpc_timeout() {
+    let (local_con, remote_con) = ChannelConnection::new_test_pair();
+    let (stop_handle, join_handle) = start_server(remote_con, Arc::new(AtomicUsize::new(0)));
+    let client = RpcClient::new(local_con);
+
+    let timeout = Duration::from_millis(20);
+    assert!(matches!(client.call("sleep", &[200], timeout), Err(Error::Timeout(t)) if t == timeout));
+
+    // the late response of the timed out call is discarded
+    assert_eq!(client.call("echo", b"next", TIMEOUT).unwrap(), b"next");
+
+    stop_handle.stop();
+    join_handle.join().unwrap().unwrap();
+}
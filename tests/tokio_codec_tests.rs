@@ -0,0 +1,74 @@
+#![cfg(feature = "tokio-codec")]
+
+use std::thread;
+
+use futures::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio_util::{
+    bytes::BytesMut,
+    codec::{Decoder, Encoder, Framed},
+};
+use xs_rust_library::{
+    connection::Connection,
+    packet_connection::{self, packet_assembly, tokio_codec::PacketFrameCodec, PacketConnection},
+};
+
+#[tokio::test]
+async fn framed_interop() {
+    let listener = TcpListener::bind("127.0.0.1:7010").await.unwrap();
+
+    let join_handle = thread::spawn(|| {
+        let mut remote_con = PacketConnection::new(std::net::TcpStream::connect("127.0.0.1:7010").unwrap(), 1024);
+        remote_con.send(b"test123").unwrap();
+        remote_con.send(&[5_u8; 100 * 1024]).unwrap();
+        remote_con.receive().unwrap()
+    });
+
+    let mut framed = Framed::new(listener.accept().await.unwrap().0, PacketFrameCodec::new());
+    assert_eq!(framed.next().await.unwrap().unwrap(), b"test123");
+    assert_eq!(framed.next().await.unwrap().unwrap(), [5_u8; 100 * 1024]);
+    framed.send(b"abc".as_slice()).await.unwrap();
+
+    assert_eq!(join_handle.join().unwrap(), b"abc");
+}
+
+#[test]
+fn encode_decode() {
+    let mut codec = PacketFrameCodec::new();
+    let mut buffer = BytesMut::new();
+    codec.encode(b"abc".as_slice(), &mut buffer).unwrap();
+    codec.encode(b"".as_slice(), &mut buffer).unwrap();
+    assert_eq!(&buffer[..], &[3, 0, 0, 0, b'a', b'b', b'c', 0, 0, 0, 0]);
+
+    let mut partial = buffer.split_to(5);
+    assert!(codec.decode(&mut partial).unwrap().is_none());
+    assert_eq!(codec.decode(&mut buffer).unwrap().unwrap(), b"abc");
+    assert_eq!(codec.decode(&mut buffer).unwrap().unwrap(), b"");
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+}
+
+#[test]
+fn max_frame_length() {
+    let mut codec = PacketFrameCodec::with_max_frame_length(4);
+    let mut buffer = BytesMut::new();
+    assert!(matches!(
+        codec.encode([0_u8; 5].as_slice(), &mut buffer),
+        Err(packet_connection::Error::PayloadTooLarge { size: 5, limit: 4 })
+    ));
+
+    let mut oversized = BytesMut::from(&[5_u8, 0, 0, 0][..]);
+    assert!(matches!(
+        codec.decode(&mut oversized),
+        Err(packet_connection::Error::PacketAssembly(packet_assembly::Error::PacketTooLarge {
+            announced: 5,
+            limit: 4
+        }))
+    ));
+}
+
+#[test]
+fn truncated_frame_at_eof() {
+    let mut codec = PacketFrameCodec::new();
+    let mut buffer = BytesMut::from(&[3_u8, 0, 0, 0, 1][..]);
+    assert!(codec.decode_eof(&mut buffer).is_err());
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "serde")]
+#![cfg_attr(not(all(feature = "bincode", feature = "json", feature = "msgpack")), allow(unused_imports))]
+
+mod util;
+
+use std::{io::Cursor, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use util::test_connections::ChannelConnection;
+use xs_rust_library::{
+    connection::Connection,
+    packet_connection::PacketConnection,
+    typed_connection::{codec::Codec, Error, TypedConnection},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Ping(u64),
+    Chat { sender: String, text: String },
+    Blob(Vec<u8>),
+}
+
+fn messages() -> Vec<Message> {
+    vec![
+        Message::Ping(42),
+        Message::Chat {
+            sender: "alice".into(),
+            text: "hello".into(),
+        },
+        Message::Blob(vec![7; 4096]),
+    ]
+}
+
+fn round_trip<Cod: Codec + Clone>(codec: Cod) {
+    let (local, remote) = ChannelConnection::new_test_pair();
+    let mut local_con = TypedConnection::<_, Message, _>::new(local, codec.clone());
+    let mut remote_con = TypedConnection::<_, Message, _>::new(remote, codec);
+
+    for message in messages() {
+        local_con.send(&message).unwrap();
+        assert_eq!(remote_con.receive().unwrap(), message);
+    }
+    assert!(remote_con.try_receive().unwrap().is_none());
+    assert!(remote_con.receive_timeout(Duration::from_millis(10)).unwrap().is_none());
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn bincode_round_trip() {
+    round_trip(xs_rust_library::typed_connection::codec::BincodeCodec);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip() {
+    round_trip(xs_rust_library::typed_connection::codec::JsonCodec);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn msgpack_round_trip() {
+    round_trip(xs_rust_library::typed_connection::codec::MessagePackCodec);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn packet_connection() {
+    use xs_rust_library::typed_connection::codec::JsonCodec;
+
+    let mut writing_con = TypedConnection::<_, Message, _>::new(PacketConnection::new(Cursor::new(Vec::new()), 1024), JsonCodec);
+    writing_con.send(&Message::Ping(1)).unwrap();
+
+    let written = writing_con.get_underlying_connection().stream_mut().get_mut().clone();
+    let mut reading_con = TypedConnection::<_, Message, _>::new(PacketConnection::new(Cursor::new(written), 1024), JsonCodec);
+    assert_eq!(reading_con.receive().unwrap(), Message::Ping(1));
+    assert!(matches!(reading_con.receive(), Err(Error::Connection(_))));
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn encrypted_connection() {
+    use std::{
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use util::test_connections::new_aes_encrypted_connection_test_pair;
+    use xs_rust_library::typed_connection::codec::BincodeCodec;
+
+    let listener = TcpListener::bind("127.0.0.1:7012").unwrap();
+    let join_handle = thread::spawn(|| PacketConnection::new(TcpStream::connect("127.0.0.1:7012").unwrap(), 64 * 1024));
+    let local = PacketConnection::new(listener.accept().unwrap().0, 64 * 1024);
+    let remote = join_handle.join().unwrap();
+
+    let (local, remote) = new_aes_encrypted_connection_test_pair(local, remote);
+    let join_handle = thread::spawn(move || {
+        let mut remote_con = TypedConnection::<_, Message, _>::new(remote, BincodeCodec);
+        for _ in 0..messages().len() {
+            let message = remote_con.receive().unwrap();
+            remote_con.send(&message).unwrap();
+        }
+    });
+
+    let mut local_con = TypedConnection::<_, Message, _>::new(local, BincodeCodec);
+    for message in messages() {
+        local_con.send(&message).unwrap();
+        assert_eq!(local_con.receive().unwrap(), message);
+    }
+    join_handle.join().unwrap();
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn decode_failure() {
+    use xs_rust_library::typed_connection::codec::JsonCodec;
+
+    let (mut local, remote) = ChannelConnection::new_test_pair();
+    let mut remote_con = TypedConnection::<_, Message, _>::new(remote, JsonCodec);
+
+    local.send(b"{not json").unwrap();
+    local.send(br#"{"Unknown": 1}"#).unwrap();
+    local.send(br#"{"Ping": 5}"#).unwrap();
+
+    assert!(matches!(remote_con.receive(), Err(Error::Decode(_))));
+    assert!(matches!(remote_con.try_receive(), Err(Error::Decode(_))));
+    assert_eq!(remote_con.receive().unwrap(), Message::Ping(5));
+}
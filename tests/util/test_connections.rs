@@ -3,12 +3,13 @@
 use std::{
     fmt::Display,
     net::{TcpListener, TcpStream},
-    sync::mpsc::{Receiver, Sender},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError},
     thread,
+    time::Duration,
 };
 
 use xs_rust_library::{
-    connection::Connection,
+    connection::{Connection, TimedReceive},
     encrypted_connection::EncryptedConnection,
     encryption::aes256_crypto::Aes256Crypto,
     key_exchange::{curve25519::Curve25519, HandshakeMode},
@@ -32,6 +33,24 @@ impl Connection for ChannelConnection {
     }
 }
 
+impl TimedReceive for ChannelConnection {
+    fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(v) => Ok(Some(v.into_vec())),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Vec<u8>>, String> {
+        match self.receiver.try_recv() {
+            Ok(v) => Ok(Some(v.into_vec())),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
 impl ChannelConnection {
     pub fn new_test_pair() -> (ChannelConnection, ChannelConnection) {
         let (sender_local, receiver_remote) = std::sync::mpsc::channel::<Box<[u8]>>();
@@ -51,36 +70,25 @@ impl ChannelConnection {
     }
 }
 
-pub struct FaultyConnection;
-
-impl Connection for FaultyConnection {
-    type ErrorType = String;
-
-    fn send(&mut self, _data: &[u8]) -> Result<(), String> {
-        Ok(())
-    }
-
-    fn receive(&mut self) -> Result<Vec<u8>, String> {
-        Ok(Vec::new())
-    }
-}
+// the performance tests send packets bigger than the default limit
+const PERFORMANCE_MAX_PACKET_SIZE: usize = 128 * 1024 * 1024;
 
 pub fn new_packet_connection_test_pair() -> (PacketConnection, PacketConnection) {
     let listener = TcpListener::bind("127.0.0.1:1234").unwrap();
 
     let join_handle = thread::spawn(move || {
         let remote_stream = TcpStream::connect("127.0.0.1:1234").unwrap();
-        PacketConnection::new(remote_stream, 1024)
+        PacketConnection::new(remote_stream, 1024).with_max_packet_size(PERFORMANCE_MAX_PACKET_SIZE)
     });
 
     let (local_stream, _) = listener.accept().unwrap();
-    let local_con = PacketConnection::new(local_stream, 1024);
+    let local_con = PacketConnection::new(local_stream, 1024).with_max_packet_size(PERFORMANCE_MAX_PACKET_SIZE);
 
     let remote_con = join_handle.join().unwrap();
     (local_con, remote_con)
 }
 
-pub fn new_aes_encrypted_connection_test_pair<E: Display, Con: Connection<ErrorType = E> + Send + Sync + 'static>(
+pub fn new_aes_encrypted_connection_test_pair<E: Display, Con: Connection<ErrorType = E> + Send + 'static>(
     local: Con,
     remote: Con,
 ) -> (EncryptedConnection<Aes256Crypto, Con>, EncryptedConnection<Aes256Crypto, Con>) {